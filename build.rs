@@ -0,0 +1,36 @@
+//! Embeds the per-library catalog files of `data/catalog` in native builds.
+//!
+//! The browser downloads them as the user picks libraries instead, so the
+//! wasm build only gets the manifest and stays the same size as libraries are added.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const CATALOG_DIR: &str = "data/catalog";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", CATALOG_DIR);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let mut files = Vec::new();
+    if env::var("CARGO_CFG_TARGET_ARCH").as_deref() != Ok("wasm32") {
+        let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(CATALOG_DIR);
+        for file in fs::read_dir(&dir).unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e)) {
+            let path = file.unwrap().path();
+            if path.extension().is_some_and(|extension| extension == "bin") {
+                files.push(path);
+            }
+        }
+        files.sort();
+    }
+
+    let mut code = String::from("&[\n");
+    for path in &files {
+        let name = path.file_name().unwrap().to_string_lossy();
+        code += &format!("    ({:?}, include_bytes!({:?})),\n", name, path.display().to_string());
+    }
+    code += "]\n";
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("catalog_files.rs");
+    fs::write(&out, code).unwrap_or_else(|e| panic!("Failed to write {}: {}", out.display(), e));
+}
@@ -0,0 +1,197 @@
+//! Keeps downloaded reaction data around so redraws do not refetch it.
+//!
+//! Entries are keyed by where their source reads them from and the release of
+//! their library, so a different mirror, URL template or library version is
+//! read afresh. Every process shares one in-memory store and in the browser the
+//! data is also persisted in IndexedDB, so reloading the page does not download
+//! it again either.
+use std::sync::Mutex;
+use async_trait::async_trait;
+use cached::{Cached as _, SizedCache};
+use lazy_static::lazy_static;
+use tracing::{debug, error};
+
+use crate::catalog::Entry;
+use crate::error::FetchError;
+use crate::fetch::ReactionData;
+use crate::library::registry;
+use crate::source::DataSource;
+
+/// Number of reactions kept in memory, the least recently used is dropped first.
+const MEMORY_CAPACITY: usize = 64;
+
+lazy_static! {
+    static ref MEMORY: Mutex<SizedCache<String, ReactionData>> = Mutex::new(SizedCache::with_size(MEMORY_CAPACITY));
+}
+
+/// Wraps a [`DataSource`] so each file is only read from it once.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cached<S> {
+    pub source: S,
+}
+
+impl<S> Cached<S> {
+    pub const fn new(source: S) -> Self {
+        Self { source }
+    }
+}
+
+#[async_trait(?Send)]
+impl<S: DataSource> DataSource for Cached<S> {
+    fn locate(&self, entry: &Entry) -> Option<String> {
+        self.source.locate(entry)
+    }
+
+    async fn fetch(&self, entry: &Entry) -> Result<ReactionData, FetchError> {
+        let Some(key) = cache_key(&self.source, entry) else {
+            return self.source.fetch(entry).await;
+        };
+        if let Some(reaction_data) = memory_get(&key) {
+            debug!("{} found in memory", key);
+            return Ok(reaction_data);
+        }
+        if let Some(reaction_data) = persistent::get(&key).await {
+            debug!("{} found in persistent cache", key);
+            memory_set(&key, &reaction_data);
+            return Ok(reaction_data);
+        }
+
+        let reaction_data = self.source.fetch(entry).await?;
+        memory_set(&key, &reaction_data);
+        persistent::set(&key, &reaction_data).await;
+        Ok(reaction_data)
+    }
+}
+
+/// The library's version followed by where `source` reads the entry from, which
+/// names the source and follows the library's base URL and URL template. `None`
+/// for entries the source cannot locate.
+fn cache_key<S: DataSource>(source: &S, entry: &Entry) -> Option<String> {
+    let location = source.locate(entry)?;
+    let version = registry().get(&entry.library).map(|library| library.version.clone()).unwrap_or_default();
+    Some(format!("{} {}", version, location))
+}
+
+fn memory_get(key: &str) -> Option<ReactionData> {
+    match MEMORY.lock() {
+        Ok(mut memory) => memory.cache_get(key).cloned(),
+        Err(e) => {
+            error!("Error reading memory cache: {:?}", e);
+            None
+        }
+    }
+}
+
+fn memory_set(key: &str, reaction_data: &ReactionData) {
+    match MEMORY.lock() {
+        Ok(mut memory) => {
+            memory.cache_set(key.to_string(), reaction_data.clone());
+        }
+        Err(e) => error!("Error writing memory cache: {:?}", e),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod persistent {
+    use std::rc::Rc;
+
+    use futures::future::{FutureExt, LocalBoxFuture, Shared};
+    use rexie::{ObjectStore, Rexie, TransactionMode};
+    use tracing::error;
+    use wasm_bindgen::JsValue;
+
+    use crate::fetch::ReactionData;
+
+    const NAME: &str = "xsplot";
+    /// Bumped whenever the keys or values change, dropping the stores of older versions
+    const VERSION: u32 = 2;
+    const STORE: &str = "files";
+
+    thread_local! {
+        /// Opened on first use and shared by every read and write after it, `None` when IndexedDB is unavailable
+        static DATABASE: Shared<LocalBoxFuture<'static, Option<Rc<Rexie>>>> = open().boxed_local().shared();
+    }
+
+    async fn open() -> Option<Rc<Rexie>> {
+        let rexie = Rexie::builder(NAME)
+            .version(VERSION)
+            .add_object_store(ObjectStore::new(STORE))
+            .build()
+            .await;
+        match rexie {
+            Ok(rexie) => Some(Rc::new(rexie)),
+            Err(e) => {
+                error!("Error opening IndexedDB cache: {:?}", e);
+                None
+            }
+        }
+    }
+
+    async fn try_get(rexie: &Rexie, key: &str) -> rexie::Result<Option<JsValue>> {
+        let transaction = rexie.transaction(&[STORE], TransactionMode::ReadOnly)?;
+        let value = transaction.store(STORE)?.get(JsValue::from_str(key)).await?;
+        transaction.done().await?;
+        Ok(value)
+    }
+
+    async fn try_set(rexie: &Rexie, key: &str, value: &JsValue) -> rexie::Result<()> {
+        let transaction = rexie.transaction(&[STORE], TransactionMode::ReadWrite)?;
+        transaction.store(STORE)?.put(value, Some(&JsValue::from_str(key))).await?;
+        transaction.done().await?;
+        Ok(())
+    }
+
+    pub async fn get(key: &str) -> Option<ReactionData> {
+        let rexie = DATABASE.with(Clone::clone).await?;
+        match try_get(&rexie, key).await {
+            Ok(value) => value.and_then(|value| serde_wasm_bindgen::from_value(value).ok()),
+            Err(e) => {
+                error!("Error reading IndexedDB cache: {:?}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn set(key: &str, reaction_data: &ReactionData) {
+        let Some(rexie) = DATABASE.with(Clone::clone).await else {
+            return;
+        };
+        let value = match serde_wasm_bindgen::to_value(reaction_data) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Error converting {} for IndexedDB: {:?}", key, e);
+                return;
+            }
+        };
+        // A full quota only costs a download next time
+        if let Err(e) = try_set(&rexie, key, &value).await {
+            error!("Error writing IndexedDB cache: {:?}", e);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod persistent {
+    use crate::fetch::ReactionData;
+
+    pub async fn get(_key: &str) -> Option<ReactionData> {
+        None
+    }
+
+    pub async fn set(_key: &str, _reaction_data: &ReactionData) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::EntryKey;
+    use crate::source::BaseUrl;
+
+    #[test]
+    fn files_are_kept_apart_by_source_and_library_version() {
+        let entry = Entry::from_key(&"Fe_56_FENDL-3.2c_n_102_294K".parse::<EntryKey>().unwrap());
+        let key = cache_key(&BaseUrl::new("https://example.org/a"), &entry).unwrap();
+        assert_eq!(key, "3.2c https://example.org/a/Fe_56_FENDL-3.2c_n_102_294K.json");
+        assert_ne!(key, cache_key(&BaseUrl::new("https://example.org/b"), &entry).unwrap());
+    }
+}
@@ -0,0 +1,925 @@
+//! The catalog of available reactions, one [`Entry`] per downloadable cross section.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::library::registry;
+use crate::reactions::{get_reaction_name_map, particle_letter, reaction_label};
+
+/// Every reaction known to the plotter, in catalog order.
+///
+/// The entries are kept in the parts they were added in, typically one per
+/// library, each indexed by key. Clones share the parts, so adding a library to
+/// a copy of the catalog leaves the others as they are. Keys are unique across
+/// parts, which [`Data::new`] and [`Data::extend`] check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Data {
+    parts: Vec<Arc<Part>>,
+    /// Bumped whenever entries are added or removed
+    generation: u64,
+}
+
+/// Entries added to the catalog together, indexed by their keys with the text interned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Part {
+    entries: Vec<Entry>,
+    /// The number each element, library, projectile and temperature has in `index`
+    strings: HashMap<Arc<str>, u32>,
+    /// Position of each entry in `entries`
+    index: HashMap<PackedKey, usize>,
+}
+
+/// An entry's key with its text replaced by the numbers [`Part::strings`] gives it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct PackedKey {
+    element: u32,
+    library: u32,
+    projectile: u32,
+    temperature: u32,
+    nucleons: i32,
+    mt: i32,
+    isomer: u8,
+}
+
+/// The fields of a key, borrowed from an [`Entry`] or an [`EntryKey`].
+struct KeyFields<'a> {
+    element: &'a str,
+    nucleons: i32,
+    isomer: u8,
+    library: &'a str,
+    projectile: &'a str,
+    mt: i32,
+    temperature: &'a str,
+}
+
+impl<'a> From<&'a Entry> for KeyFields<'a> {
+    fn from(entry: &'a Entry) -> Self {
+        Self {
+            element: &entry.element,
+            nucleons: entry.nucleons,
+            isomer: entry.isomer,
+            library: &entry.library,
+            projectile: &entry.projectile,
+            mt: entry.mt,
+            temperature: &entry.temperature,
+        }
+    }
+}
+
+impl<'a> From<&'a EntryKey> for KeyFields<'a> {
+    fn from(key: &'a EntryKey) -> Self {
+        Self {
+            element: &key.element,
+            nucleons: key.nucleons,
+            isomer: key.isomer,
+            library: &key.library,
+            projectile: &key.projectile,
+            mt: key.mt,
+            temperature: &key.temperature,
+        }
+    }
+}
+
+impl Part {
+    /// Indexes the entries, failing if two have the same key.
+    fn new(entries: Vec<Entry>) -> Result<Self, String> {
+        let mut part = Self { entries: Vec::new(), strings: HashMap::new(), index: HashMap::with_capacity(entries.len()) };
+        for (i, entry) in entries.iter().enumerate() {
+            let mut intern = |text: &Arc<str>| {
+                let next = part.strings.len() as u32;
+                *part.strings.entry(text.clone()).or_insert(next)
+            };
+            let key = PackedKey {
+                element: intern(&entry.element),
+                library: intern(&entry.library),
+                projectile: intern(&entry.projectile),
+                temperature: intern(&entry.temperature),
+                nucleons: entry.nucleons,
+                mt: entry.mt,
+                isomer: entry.isomer,
+            };
+            if part.index.insert(key, i).is_some() {
+                return Err(format!("{} is listed twice in the catalog", entry.key()));
+            }
+        }
+        part.entries = entries;
+        Ok(part)
+    }
+
+    /// The packed form of a key, `None` when some of its text is in no entry.
+    fn pack(&self, key: KeyFields) -> Option<PackedKey> {
+        Some(PackedKey {
+            element: *self.strings.get(key.element)?,
+            library: *self.strings.get(key.library)?,
+            projectile: *self.strings.get(key.projectile)?,
+            temperature: *self.strings.get(key.temperature)?,
+            nucleons: key.nucleons,
+            mt: key.mt,
+            isomer: key.isomer,
+        })
+    }
+
+    fn position(&self, key: KeyFields) -> Option<usize> {
+        self.pack(key).and_then(|key| self.index.get(&key).copied())
+    }
+
+    /// Removes the entry at `position`, moving the ones after it up.
+    fn remove(&mut self, position: usize) {
+        let entry = self.entries.remove(position);
+        if let Some(key) = self.pack((&entry).into()) {
+            self.index.remove(&key);
+        }
+        for i in self.index.values_mut().filter(|i| **i > position) {
+            *i -= 1;
+        }
+    }
+}
+
+/// A single reaction of a nuclide in one evaluated library at one temperature.
+///
+/// The text fields repeat across thousands of entries, so entries read from
+/// the same catalog share them rather than each holding a copy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Row index in the catalog, which changes whenever the catalog is regenerated.
+    /// Use [`Entry::key`] for anything that is saved or shared.
+    pub id: i32,
+    pub element: Arc<str>,
+    pub nucleons: i32,
+    /// Metastable state, 0 for the ground state and 1 for the first isomer
+    pub isomer: u8,
+    pub library: Arc<str>,
+    /// Incident particle as stored in the file names, e.g. `n` or `p`, see [`projectile_symbol`](crate::reactions::projectile_symbol)
+    pub projectile: Arc<str>,
+    pub reaction: Arc<str>,
+    pub mt: i32,
+    pub temperature: Arc<str>,
+}
+
+/// Identifies a reaction by its content, so it stays valid when the catalog is regenerated.
+///
+/// Its text form is `{element}_{nucleons}_{library}_{projectile}_{mt}_{temperature}K`,
+/// e.g. `Fe_56_FENDL-3.2c_n_102_294K`, with the nucleons followed by the
+/// metastable state for isomers, e.g. `Am_242m1_ENDFB-8.0_n_102_294K`, which
+/// is also the stem of the reaction's file, see [`convert_string`](crate::fetch::convert_string).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryKey {
+    pub element: String,
+    pub nucleons: i32,
+    pub isomer: u8,
+    pub library: String,
+    pub projectile: String,
+    pub mt: i32,
+    pub temperature: String,
+}
+
+/// The nucleons with the metastable state of isomers, e.g. `56` or `242m1`.
+fn nucleons_text(nucleons: i32, isomer: u8) -> String {
+    match isomer {
+        0 => nucleons.to_string(),
+        isomer => format!("{}m{}", nucleons, isomer),
+    }
+}
+
+impl EntryKey {
+    /// The nuclide, e.g. `Fe56` or `Am242m1`.
+    pub fn nuclide(&self) -> String {
+        format!("{}{}", self.element, nucleons_text(self.nucleons, self.isomer))
+    }
+}
+
+impl fmt::Display for EntryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nucleons = nucleons_text(self.nucleons, self.isomer);
+        write!(f, "{}_{}_{}_{}_{}_{}K", self.element, nucleons, self.library, self.projectile, self.mt, self.temperature)
+    }
+}
+
+impl FromStr for EntryKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('_').collect();
+        if parts.len() < 6 {
+            return Err(format!("Entry key {} does not have the form element_nucleons_library_projectile_mt_temperatureK", s));
+        }
+        // The library sits in the middle so it is the only part allowed to contain underscores
+        let n = parts.len();
+        let temperature = parts[n - 1].strip_suffix('K').ok_or_else(|| format!("Entry key {} does not end in a temperature in K", s))?;
+        let (nucleons, isomer) = parts[1].split_once('m').unwrap_or((parts[1], "0"));
+        let invalid_nucleons = || format!("Entry key {} has invalid nucleons {}", s, parts[1]);
+        Ok(EntryKey {
+            element: parts[0].to_string(),
+            nucleons: nucleons.parse().map_err(|_| invalid_nucleons())?,
+            isomer: isomer.parse().map_err(|_| invalid_nucleons())?,
+            library: parts[2..n - 3].join("_"),
+            projectile: parts[n - 3].to_string(),
+            mt: parts[n - 2].parse().map_err(|_| format!("Entry key {} has invalid MT {}", s, parts[n - 2]))?,
+            temperature: temperature.to_string(),
+        })
+    }
+}
+
+impl Serialize for EntryKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Entry {
+    /// The stable key of this entry.
+    pub fn key(&self) -> EntryKey {
+        EntryKey {
+            element: self.element.to_string(),
+            nucleons: self.nucleons,
+            isomer: self.isomer,
+            library: self.library.to_string(),
+            projectile: self.projectile.to_string(),
+            mt: self.mt,
+            temperature: self.temperature.to_string(),
+        }
+    }
+
+    /// The entry `key` identifies, for reactions of libraries that are not in the catalog
+    /// until their index is read.
+    pub fn from_key(key: &EntryKey) -> Self {
+        Entry {
+            id: -1,
+            element: key.element.as_str().into(),
+            nucleons: key.nucleons,
+            isomer: key.isomer,
+            library: key.library.as_str().into(),
+            projectile: key.projectile.as_str().into(),
+            reaction: reaction_label(&key.projectile, key.mt, &get_reaction_name_map()).into(),
+            mt: key.mt,
+            temperature: key.temperature.as_str().into(),
+        }
+    }
+
+    /// Whether this entry is `key`'s reaction of the same nuclide and state, in
+    /// any library and at any temperature.
+    fn is_reaction_of(&self, key: &EntryKey) -> bool {
+        self.mt == key.mt
+            && self.nucleons == key.nucleons
+            && self.isomer == key.isomer
+            && *self.element == *key.element
+            && *self.projectile == *key.projectile
+    }
+
+    /// Whether this entry is the one `key` identifies.
+    pub fn has_key(&self, key: &EntryKey) -> bool {
+        *self.element == *key.element
+            && self.nucleons == key.nucleons
+            && self.isomer == key.isomer
+            && *self.library == *key.library
+            && *self.projectile == *key.projectile
+            && self.mt == key.mt
+            && *self.temperature == *key.temperature
+    }
+}
+
+/// One library's part of the catalog, a file in `data/catalog`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestLibrary {
+    /// As in the [registry](crate::library)
+    pub name: String,
+    /// The compact catalog file, relative to `data/catalog`
+    pub file: String,
+    pub entries: usize,
+}
+
+/// The libraries the catalog is split into, written by `xsplot build-index`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub libraries: Vec<ManifestLibrary>,
+}
+
+impl Manifest {
+    pub fn get(&self, name: &str) -> Option<&ManifestLibrary> {
+        self.libraries.iter().find(|library| library.name == name)
+    }
+}
+
+/// The library files `build.rs` embedded, which is all of them natively and none in the
+/// browser, where they are downloaded as the user asks for each library instead.
+const EMBEDDED: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/catalog_files.rs"));
+
+impl Default for Data {
+    /// The libraries embedded in the build, in manifest order.
+    fn default() -> Self {
+        let mut data = Self::new(Vec::new()).unwrap();
+        for library in &manifest().libraries {
+            if let Some((_, bytes)) = EMBEDDED.iter().find(|(file, _)| *file == library.file) {
+                let entries = load_data_from_binary(bytes, data.len() as i32).expect("Failed to load the embedded catalog");
+                data.extend(entries).expect("The embedded catalog lists a reaction twice");
+            }
+        }
+        data
+    }
+}
+
+lazy_static! {
+    static ref MANIFEST: Manifest = serde_json::from_str(include_str!("../data/catalog/manifest.json")).expect("Failed to load the catalog manifest");
+    static ref SHARED: Data = Data::default();
+}
+
+pub fn manifest() -> &'static Manifest {
+    &MANIFEST
+}
+
+/// The embedded catalog, decoded on first use and shared from then on.
+pub fn catalog() -> &'static Data {
+    &SHARED
+}
+
+impl Data {
+    /// Indexes the entries by key, failing if two have the same key.
+    pub fn new(data: Vec<Entry>) -> Result<Self, String> {
+        let mut new = Self { parts: Vec::new(), generation: 0 };
+        new.extend(data)?;
+        Ok(new)
+    }
+
+    /// Appends entries as a part of their own, failing without adding any if one has the key of another.
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = Entry>) -> Result<(), String> {
+        let part = Part::new(entries.into_iter().collect())?;
+        if let Some(entry) = part.entries.iter().find(|entry| self.parts.iter().any(|other| other.position((*entry).into()).is_some())) {
+            return Err(format!("{} is listed twice in the catalog", entry.key()));
+        }
+        if !part.entries.is_empty() {
+            self.parts.push(Arc::new(part));
+        }
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Removes the entry with this row index, copying the part it was in if a clone shares it.
+    pub fn remove(&mut self, id: i32) {
+        for part in &mut self.parts {
+            if let Some(position) = part.entries.iter().position(|entry| entry.id == id) {
+                Arc::make_mut(part).remove(position);
+                self.generation += 1;
+                return;
+            }
+        }
+    }
+
+    /// Changes whenever entries are added or removed, so what was worked out
+    /// from the catalog can be kept until then.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Every entry, in catalog order.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.parts.iter().flat_map(|part| part.entries.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.parts.iter().map(|part| part.entries.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Looks up an entry by its row index, only meaningful within one build of the catalog.
+    pub fn get(&self, id: i32) -> Option<&Entry> {
+        self.iter().find(|entry| entry.id == id)
+    }
+
+    /// Looks up an entry by its stable key.
+    pub fn get_by_key(&self, key: &EntryKey) -> Option<&Entry> {
+        self.parts.iter().find_map(|part| part.position(key.into()).map(|i| &part.entries[i]))
+    }
+
+    /// Whether any entry is from the library with this name.
+    pub fn has_library(&self, name: &str) -> bool {
+        self.iter().any(|entry| &*entry.library == name)
+    }
+
+    /// The id the next added entry gets.
+    pub fn next_id(&self) -> i32 {
+        self.iter().map(|entry| entry.id + 1).max().unwrap_or(0)
+    }
+
+    /// The same reaction as `key` in every library in the catalog, `key`'s own entry included,
+    /// in registry order so comparisons always list the libraries alike.
+    pub fn in_all_libraries(&self, key: &EntryKey) -> Vec<&Entry> {
+        let registry = registry();
+        let rank = |entry: &Entry| registry.libraries.iter().position(|library| *library.name == *entry.library);
+        let mut entries: Vec<&Entry> = self.iter().filter(|entry| entry.is_reaction_of(key) && *entry.temperature == *key.temperature).collect();
+        // Libraries missing from the registry go last
+        entries.sort_by_key(|entry| rank(entry).unwrap_or(usize::MAX));
+        entries
+    }
+
+    /// The same reaction as `key` at every temperature in the catalog, `key`'s own entry included.
+    pub fn at_all_temperatures(&self, key: &EntryKey) -> Vec<&Entry> {
+        self.iter().filter(|entry| entry.is_reaction_of(key) && *entry.library == *key.library).collect()
+    }
+}
+
+/// The columns of the catalog CSV, in the order they are written.
+pub const CSV_HEADERS: [&str; 8] = ["id", "element", "nucleons", "isomer", "library", "incident_particle", "mt", "temperature"];
+
+/// Other names accepted for a column when reading.
+const CSV_ALIASES: [(&str, &str); 5] =
+    [("projectile", "incident_particle"), ("mt_number", "mt"), ("temperature_k", "temperature"), ("mass_number", "nucleons"), ("metastable", "isomer")];
+
+/// Where each known column is in a CSV header.
+struct CsvColumns {
+    positions: HashMap<&'static str, usize>,
+}
+
+impl CsvColumns {
+    /// Columns are matched ignoring case, surrounding spaces and order, and
+    /// columns that are not known are skipped.
+    fn new(headers: &csv::StringRecord) -> Result<Self, String> {
+        let mut positions = HashMap::new();
+        for (position, header) in headers.iter().enumerate() {
+            let header = header.trim().to_lowercase();
+            let name = CSV_ALIASES.iter().find(|(alias, _)| *alias == header).map(|(_, name)| *name).unwrap_or(&header);
+            if let Some(column) = CSV_HEADERS.iter().find(|column| **column == name) {
+                if positions.insert(*column, position).is_some() {
+                    return Err(format!("line 1: column {} appears more than once", column));
+                }
+            }
+        }
+        // Catalogs from before other projectiles and ids only hold neutron reactions in row order,
+        // and ones from before isomers list them after the ground state
+        let missing: Vec<&str> = CSV_HEADERS
+            .iter()
+            .filter(|column| !positions.contains_key(*column) && !matches!(**column, "id" | "isomer" | "incident_particle"))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("line 1: missing columns {}, expected {}", missing.join(", "), CSV_HEADERS.join(",")));
+        }
+        Ok(Self { positions })
+    }
+
+    fn has(&self, column: &str) -> bool {
+        self.positions.contains_key(column)
+    }
+
+    fn get<'a>(&self, record: &'a csv::StringRecord, column: &str) -> Option<&'a str> {
+        self.positions.get(column).and_then(|position| record.get(*position)).map(str::trim)
+    }
+}
+
+/// Parses a catalog CSV such as the one `xsplot build-index --csv` writes.
+///
+/// Fields may be quoted and columns can come in any order, with unknown ones
+/// ignored. Errors give the line of the CSV they were found on. Without an
+/// isomer column, metastable states are numbered by [`number_isomers`].
+pub fn load_data_from_csv(csv_data: &str) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let reaction_name = get_reaction_name_map();
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv_data.as_bytes());
+    let headers = reader.headers().map_err(|e| format!("line 1: {}", e))?.clone();
+    if headers.iter().all(|header| header.trim().is_empty()) {
+        return Err("line 1: the CSV has no header".into());
+    }
+    let columns = CsvColumns::new(&headers)?;
+
+    let mut data = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| match e.position() {
+            Some(position) => format!("line {}: {}", position.line(), e),
+            None => e.to_string(),
+        })?;
+        let line = record.position().map_or(0, |position| position.line());
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if record.len() != headers.len() {
+            return Err(format!("line {}: found {} fields, the header has {}", line, record.len(), headers.len()).into());
+        }
+        let text = |column: &str| -> Result<&str, String> {
+            match columns.get(&record, column) {
+                Some("") => Err(format!("line {}: {} is empty", line, column)),
+                Some(value) => Ok(value),
+                None => Err(format!("line {}: no {}", line, column)),
+            }
+        };
+        let number = |column: &str| -> Result<i32, String> {
+            let value = text(column)?;
+            value.parse().map_err(|_| format!("line {}: {} {:?} is not a whole number", line, column, value))
+        };
+
+        let mt = number("mt")?;
+        let projectile = if columns.has("incident_particle") { text("incident_particle")? } else { "n" };
+        let id = if columns.has("id") { number("id")? } else { data.len() as i32 };
+        let isomer = if columns.has("isomer") {
+            let isomer = number("isomer")?;
+            u8::try_from(isomer).map_err(|_| format!("line {}: isomer {} is not a metastable state", line, isomer))?
+        } else {
+            0
+        };
+        let entry = Entry {
+            id,
+            element: text("element")?.into(),
+            nucleons: number("nucleons")?,
+            isomer,
+            library: text("library")?.into(),
+            projectile: projectile.into(),
+            reaction: reaction_label(projectile, mt, &reaction_name).into(),
+            mt,
+            temperature: text("temperature")?.trim_end_matches(['K', 'k']).trim().into(),
+        };
+        data.push(entry);
+    }
+    if !columns.has("isomer") {
+        number_isomers(&mut data)?;
+    }
+    Ok(data)
+}
+
+/// Numbers the metastable states of entries read from sources that list an
+/// isomer under the same nuclide as its ground state.
+///
+/// These sources list all of a nuclide's reactions in each library, at every
+/// temperature, as one run of consecutive entries, so a further run of a
+/// nuclide is its next metastable state: the second run of Am242 is Am242m1.
+/// A reaction repeated within a run is an error, as it could only be a duplicate.
+pub fn number_isomers(entries: &mut [Entry]) -> Result<(), String> {
+    let nuclide = |entry: &Entry| (entry.element.clone(), entry.nucleons, entry.library.clone(), entry.projectile.clone());
+    let mut states = HashMap::new();
+    let mut run = None;
+    let mut reactions: Vec<(i32, Arc<str>)> = Vec::new();
+    for entry in entries {
+        let current = nuclide(entry);
+        if run.as_ref() != Some(&current) {
+            let state: &mut u8 = states.entry(current.clone()).and_modify(|state| *state += 1).or_insert(0);
+            entry.isomer = *state;
+            run = Some(current);
+            reactions.clear();
+        } else {
+            entry.isomer = states[&current];
+        }
+        let reaction = (entry.mt, entry.temperature.clone());
+        if reactions.contains(&reaction) {
+            return Err(format!("{} is listed twice", entry.key()));
+        }
+        reactions.push(reaction);
+    }
+    Ok(())
+}
+
+/// The first bytes of a compact catalog, with its version.
+const MAGIC: &[u8] = b"XSCAT2";
+const RECORD_SIZE: usize = 9;
+
+/// Writes entries in the compact form [`load_data_from_binary`] reads.
+///
+/// The layout, all integers little endian:
+///
+/// - the magic bytes `XSCAT2`
+/// - four string tables, for elements, libraries, projectiles and temperatures,
+///   each a `u8` count followed by `u8`-length-prefixed UTF-8 strings
+/// - a `u32` entry count followed by one 9 byte record per entry: the element,
+///   library, projectile and temperature as `u8` indices into their tables,
+///   the metastable state as a `u8`, then the nucleons and MT number as `u16`
+///
+/// Ids are not stored, entries are numbered by position when read.
+pub fn write_data_to_binary(entries: &[Entry]) -> Result<Vec<u8>, String> {
+    let mut tables: [Vec<&str>; 4] = Default::default();
+    let mut records = Vec::with_capacity(entries.len() * RECORD_SIZE);
+    for entry in entries {
+        for (table, value) in tables.iter_mut().zip([&entry.element, &entry.library, &entry.projectile, &entry.temperature]) {
+            let index = match table.iter().position(|existing| *existing == &**value) {
+                Some(index) => index,
+                None => {
+                    if value.len() > u8::MAX as usize {
+                        return Err(format!("{} is too long for the compact catalog", value));
+                    }
+                    // The table's length is written as a u8 too, so it holds at most 255 values
+                    if table.len() == u8::MAX as usize {
+                        return Err(format!("More than 255 distinct values such as {}", value));
+                    }
+                    table.push(value);
+                    table.len() - 1
+                }
+            };
+            records.push(index as u8);
+        }
+        records.push(entry.isomer);
+        let number = |value: i32, name: &str| u16::try_from(value).map_err(|_| format!("{} {} of {} does not fit the compact catalog", name, value, entry.key()));
+        records.extend_from_slice(&number(entry.nucleons, "Nucleons")?.to_le_bytes());
+        records.extend_from_slice(&number(entry.mt, "MT")?.to_le_bytes());
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    for table in &tables {
+        bytes.push(table.len() as u8);
+        for value in table {
+            bytes.push(value.len() as u8);
+            bytes.extend_from_slice(value.as_bytes());
+        }
+    }
+    bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&records);
+    Ok(bytes)
+}
+
+/// Reads the compact catalog written by [`write_data_to_binary`], numbering the entries from `first_id`.
+pub fn load_data_from_binary(bytes: &[u8], first_id: i32) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut rest = bytes.strip_prefix(MAGIC).ok_or("The compiled catalog does not start with XSCAT2, regenerate it with xsplot build-index")?;
+    let mut take = |n: usize| -> Result<&[u8], Box<dyn Error>> {
+        if rest.len() < n {
+            return Err("The compiled catalog is truncated".into());
+        }
+        let (taken, remaining) = rest.split_at(n);
+        rest = remaining;
+        Ok(taken)
+    };
+
+    let mut tables: Vec<Vec<Arc<str>>> = Vec::new();
+    for _ in 0..4 {
+        let count = take(1)?[0];
+        let mut table = Vec::new();
+        for _ in 0..count {
+            let len = take(1)?[0] as usize;
+            table.push(Arc::from(std::str::from_utf8(take(len)?)?));
+        }
+        tables.push(table);
+    }
+    let string = |table: usize, index: u8| -> Result<Arc<str>, Box<dyn Error>> {
+        tables[table].get(index as usize).cloned().ok_or_else(|| "The compiled catalog has an invalid string index".into())
+    };
+
+    let reaction_name = get_reaction_name_map();
+    let mut reactions: HashMap<(u8, u16), Arc<str>> = HashMap::new();
+    let count = u32::from_le_bytes(take(4)?.try_into()?);
+    let mut data = Vec::with_capacity(count as usize);
+    for id in 0..count {
+        let record = take(RECORD_SIZE)?;
+        let projectile = string(2, record[2])?;
+        let mt = u16::from_le_bytes([record[7], record[8]]);
+        let reaction = reactions
+            .entry((record[2], mt))
+            .or_insert_with(|| reaction_label(&projectile, mt.into(), &reaction_name).into())
+            .clone();
+        data.push(Entry {
+            id: first_id + id as i32,
+            element: string(0, record[0])?,
+            nucleons: u16::from_le_bytes([record[5], record[6]]).into(),
+            isomer: record[4],
+            library: string(1, record[1])?,
+            projectile,
+            reaction,
+            mt: mt.into(),
+            temperature: string(3, record[3])?,
+        });
+    }
+    Ok(data)
+}
+
+/// Writes entries in the form [`load_data_from_csv`] reads, quoting fields where needed.
+pub fn write_data_to_csv(entries: &[Entry]) -> String {
+    let mut writer = csv::WriterBuilder::new().terminator(csv::Terminator::Any(b'\n')).from_writer(Vec::new());
+    // Writing to memory cannot fail
+    writer.write_record(CSV_HEADERS).unwrap();
+    for entry in entries {
+        let (id, nucleons, isomer, mt) = (entry.id.to_string(), entry.nucleons.to_string(), entry.isomer.to_string(), entry.mt.to_string());
+        writer
+            .write_record([&*id, &*entry.element, &*nucleons, &*isomer, &*entry.library, &*entry.projectile, &*mt, &*entry.temperature])
+            .unwrap();
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+/// Reads a library's JSON index, the file `index_file` in the [registry](crate::library)
+/// points at, numbering the entries from `first_id`.
+///
+/// Particles are given by name, which are mapped to the letters the file names
+/// use by [`particle_letter`]. The indices list isomers under their ground
+/// state's nuclide, after it, and are numbered by [`number_isomers`].
+pub fn load_data_from_index(index_json: &str, first_id: i32) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let reaction_name = get_reaction_name_map();
+    let records: Vec<HashMap<String, serde_json::Value>> = serde_json::from_str(index_json)?;
+
+    let mut data = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        // Numbers are written as numbers or as strings depending on the library
+        let field = |name: &str| -> Result<String, Box<dyn Error>> {
+            match record.get(name) {
+                Some(serde_json::Value::String(value)) => Ok(value.clone()),
+                Some(serde_json::Value::Number(value)) => Ok(value.to_string()),
+                _ => Err(format!("Index record {} has no {}", i, name).into()),
+            }
+        };
+        let particle = field("Incident particle")?;
+        let projectile = particle_letter(&particle).ok_or_else(|| format!("Index record {} has an unknown incident particle {}", i, particle))?;
+        let mt: i32 = field("MT reaction number")?.parse()?;
+        let entry = Entry {
+            id: first_id + i as i32,
+            element: field("Atomic symbol")?.into(),
+            nucleons: field("Mass number")?.parse()?,
+            isomer: 0,
+            library: field("Library")?.into(),
+            reaction: reaction_label(projectile, mt, &reaction_name).into(),
+            projectile: projectile.into(),
+            mt,
+            temperature: field("Temperature(K)")?.into(),
+        };
+        data.push(entry);
+    }
+    number_isomers(&mut data)?;
+    Ok(data)
+}
+
+/// The reactions the numeric ids of old links stand for.
+///
+/// Links made before entries had keys hold row numbers of the catalog as it
+/// was then, which `data/legacy_ids.bin` keeps in compact form with its rows in
+/// id order. The file is frozen rather than regenerated with the catalog, so the
+/// ids resolve to the same reactions however the catalog changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegacyIds {
+    keys: Vec<EntryKey>,
+}
+
+impl LegacyIds {
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(Self { keys: load_data_from_binary(bytes, 0)?.iter().map(Entry::key).collect() })
+    }
+
+    /// The key of the reaction an old link's id stands for.
+    pub fn get(&self, id: i32) -> Option<&EntryKey> {
+        usize::try_from(id).ok().and_then(|id| self.keys.get(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(csv: &str) -> String {
+        load_data_from_csv(csv).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn reads_quoted_fields_and_columns_in_any_order() {
+        let csv = "Temperature, MT ,library,element,nucleons,id,projectile,comment
+\"294\",102,\"ENDFB-8.0\",Fe,56,7,n,\"capture, the one for shielding\"
+0K,2,FENDL-3.2c,\"Li\",7,8,p,
+";
+        let entries = load_data_from_csv(csv).unwrap();
+        let keys: Vec<String> = entries.iter().map(|entry| entry.key().to_string()).collect();
+        assert_eq!(keys, ["Fe_56_ENDFB-8.0_n_102_294K", "Li_7_FENDL-3.2c_p_2_0K"]);
+        assert_eq!(entries.iter().map(|entry| entry.id).collect::<Vec<_>>(), [7, 8]);
+        assert_eq!(&*entries[0].reaction, "(n,gamma)");
+    }
+
+    #[test]
+    fn older_catalogs_default_to_neutrons_in_row_order() {
+        let entries = load_data_from_csv("element,nucleons,library,mt,temperature\r\nFe,56,ENDFB-8.0,102,294\r\n\r\nLi,6,FENDL-3.2c,105,294\r\n").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[1].id, &*entries[1].projectile), (1, "n"));
+    }
+
+    #[test]
+    fn round_trips_through_the_written_csv() {
+        let entries = load_data_from_csv("id,element,nucleons,library,incident_particle,mt,temperature\n0,Fe,56,\"Odd, library\",n,102,294\n").unwrap();
+        let written = write_data_to_csv(&entries);
+        assert!(written.contains("\"Odd, library\""));
+        assert_eq!(load_data_from_csv(&written).unwrap(), entries);
+    }
+
+    #[test]
+    fn keys_round_trip_through_their_text_form() {
+        for text in ["Fe_56_FENDL-3.2c_n_102_294K", "Am_242m1_ENDFB-8.0_n_18_294K", "Li_6_My_patched_library_p_2_0K"] {
+            let key: EntryKey = text.parse().unwrap();
+            assert_eq!(key.to_string(), text);
+        }
+        let key: EntryKey = "Am_242m1_ENDFB-8.0_n_18_294K".parse().unwrap();
+        assert_eq!((key.nucleons, key.isomer, key.nuclide()), (242, 1, "Am242m1".to_string()));
+        assert!("Am_242m_ENDFB-8.0_n_18_294K".parse::<EntryKey>().is_err());
+    }
+
+    #[test]
+    fn repeated_runs_of_a_nuclide_are_isomers_and_keys_are_unique() {
+        let header = "element,nucleons,library,mt,temperature\n";
+        let entries = load_data_from_csv(&format!("{}Am,242,ENDFB-8.0,102,294\nAm,242,ENDFB-8.0,18,294\nAm,242,ENDFB-8.0,102,294\n", header));
+        assert_eq!(entries.unwrap_err().to_string(), "Am_242_ENDFB-8.0_n_102_294K is listed twice");
+
+        let entries = load_data_from_csv(&format!("{}Am,242,ENDFB-8.0,102,294\nAm,242,ENDFB-8.0,18,294\nAm,241,ENDFB-8.0,102,294\nAm,242,ENDFB-8.0,102,294\n", header)).unwrap();
+        let keys: Vec<String> = entries.iter().map(|entry| entry.key().to_string()).collect();
+        assert_eq!(keys, ["Am_242_ENDFB-8.0_n_102_294K", "Am_242_ENDFB-8.0_n_18_294K", "Am_241_ENDFB-8.0_n_102_294K", "Am_242m1_ENDFB-8.0_n_102_294K"]);
+
+        let mut data = Data::new(entries.clone()).unwrap();
+        assert_eq!(data.get_by_key(&entries[3].key()), Some(&entries[3]));
+        assert_eq!(data.extend([entries[0].clone()]), Err("Am_242_ENDFB-8.0_n_102_294K is listed twice in the catalog".to_string()));
+        assert_eq!(data.len(), 4);
+        assert!(Data::new(vec![entries[1].clone(), entries[1].clone()]).is_err());
+
+        let written = write_data_to_binary(&entries).unwrap();
+        assert_eq!(load_data_from_binary(&written, 0).unwrap(), entries);
+    }
+
+    #[test]
+    fn comparisons_keep_the_nuclide_state_and_projectile() {
+        let csv = "id,element,nucleons,isomer,library,incident_particle,mt,temperature
+0,Fe,56,0,FENDL-3.2c,n,102,294
+1,Fe,56,0,ENDFB-8.0,n,102,294
+2,Fe,56,0,FENDL-3.2c,n,102,0
+3,Fe,56,1,ENDFB-8.0,n,102,294
+4,Fe,56,0,ENDFB-8.0,p,102,294
+";
+        let data = Data::new(load_data_from_csv(csv).unwrap()).unwrap();
+        let key = data.get(0).unwrap().key();
+        let ids = |entries: Vec<&Entry>| entries.iter().map(|entry| entry.id).collect::<Vec<_>>();
+        assert_eq!(ids(data.in_all_libraries(&key)), [1, 0]);
+        assert_eq!(ids(data.at_all_temperatures(&key)), [0, 2]);
+    }
+
+    #[test]
+    fn string_tables_hold_at_most_255_values() {
+        let entries = |elements: usize| -> Vec<Entry> {
+            (0..elements)
+                .map(|i| Entry { id: i as i32, element: format!("E{}", i).into(), ..Entry::from_key(&"Fe_56_FENDL-3.2c_n_102_294K".parse::<EntryKey>().unwrap()) })
+                .collect()
+        };
+        let full = entries(255);
+        let written = write_data_to_binary(&full).unwrap();
+        assert_eq!(load_data_from_binary(&written, 0).unwrap(), full);
+        assert_eq!(write_data_to_binary(&entries(256)), Err("More than 255 distinct values such as E255".to_string()));
+    }
+
+    #[test]
+    fn removing_an_entry_leaves_clones_and_the_other_keys_alone() {
+        let entries = load_data_from_csv("element,nucleons,library,mt,temperature\nFe,56,FENDL-3.2c,102,294\nFe,56,FENDL-3.2c,2,294\nLi,6,ENDFB-8.0,105,294\n").unwrap();
+        let mut data = Data::new(entries[..2].to_vec()).unwrap();
+        data.extend(entries[2..].to_vec()).unwrap();
+        let copy = data.clone();
+        data.remove(0);
+        assert_eq!(data.get_by_key(&entries[0].key()), None);
+        assert_eq!(data.get_by_key(&entries[1].key()), Some(&entries[1]));
+        assert_eq!(data.get_by_key(&entries[2].key()), Some(&entries[2]));
+        assert_eq!(data.len(), 2);
+        assert_eq!(copy.get_by_key(&entries[0].key()), Some(&entries[0]));
+        assert_ne!(copy.generation(), data.generation());
+    }
+
+    #[test]
+    fn embedded_catalog_keys_are_unique() {
+        let data = catalog();
+        assert!(data.iter().all(|entry| data.get_by_key(&entry.key()) == Some(entry)));
+        let isomer: EntryKey = "Am_242m1_ENDFB-8.0_n_102_294K".parse().unwrap();
+        assert!(data.get_by_key(&isomer).is_some());
+    }
+
+    #[test]
+    fn embedded_files_match_the_catalog_csv() {
+        let entries = load_data_from_csv(include_str!("../data/catalog.csv")).unwrap();
+        let mut first_id = 0;
+        for library in &manifest().libraries {
+            let (_, bytes) = EMBEDDED.iter().find(|(file, _)| *file == library.file).unwrap();
+            let from_binary = load_data_from_binary(bytes, first_id).unwrap();
+            let from_csv: Vec<Entry> = entries.iter().filter(|entry| *entry.library == library.name).cloned().collect();
+            assert_eq!(from_binary.len(), library.entries, "{} does not have as many entries as the manifest says", library.file);
+            assert!(from_binary == from_csv, "{} is not generated from data/catalog.csv, run xsplot build-index data/catalog.csv", library.file);
+            first_id += from_binary.len() as i32;
+        }
+        assert_eq!(first_id as usize, entries.len());
+    }
+
+    #[test]
+    fn legacy_ids_keep_the_rows_they_had() {
+        let legacy = LegacyIds::from_binary(include_bytes!("../data/legacy_ids.bin")).unwrap();
+        for entry in load_data_from_csv(include_str!("../data/catalog.csv")).unwrap() {
+            assert_eq!(legacy.get(entry.id), Some(&entry.key()));
+        }
+        assert_eq!(legacy.get(-1), None);
+    }
+
+    #[test]
+    fn malformed_catalogs_give_the_line_at_fault() {
+        let header = "id,element,nucleons,library,incident_particle,mt,temperature\n";
+        assert_eq!(error(""), "line 1: the CSV has no header");
+        assert_eq!(
+            error("id,element,library,mt\n"),
+            "line 1: missing columns nucleons, temperature, expected id,element,nucleons,isomer,library,incident_particle,mt,temperature"
+        );
+        assert_eq!(error("element,Element,nucleons,library,mt,temperature\n"), "line 1: column element appears more than once");
+        assert_eq!(error(&format!("{}0,Fe,56,ENDFB-8.0,n,102,294\n1,Fe,56,ENDFB-8.0,n,102\n", header)), "line 3: found 6 fields, the header has 7");
+        assert_eq!(error(&format!("{}0,Fe,fifty six,ENDFB-8.0,n,102,294\n", header)), "line 2: nucleons \"fifty six\" is not a whole number");
+        assert_eq!(error(&format!("{}0,,56,ENDFB-8.0,n,102,294\n", header)), "line 2: element is empty");
+        // A quoted field spanning lines moves the lines after it along
+        assert_eq!(
+            error(&format!("{}0,Fe,56,\"ENDFB\n-8.0\",n,102,294\n1,Fe,56,ENDFB-8.0,n,MT2,294\n", header)),
+            "line 4: mt \"MT2\" is not a whole number"
+        );
+    }
+}
@@ -0,0 +1,510 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+
+use xsplot::catalog::{
+    catalog, load_data_from_csv, load_data_from_index, write_data_to_binary, write_data_to_csv, Data, Entry, EntryKey, Manifest, ManifestLibrary,
+};
+use xsplot::colours::ColourMap;
+use xsplot::error::FetchError;
+use xsplot::export::ExportFormat;
+use xsplot::fetch::{fetch_missing_libraries, fetch_plotly_js, generate_cache};
+use xsplot::figure::{figure, to_html, FigureFormat, FigureStyle};
+use xsplot::matplotlib::to_matplotlib;
+use xsplot::library::{registry, set_registry, Library, Registry};
+use xsplot::reactions::{get_reaction_name_map, projectile_symbol};
+use xsplot::source::{BaseUrl, DataSource, GitHub, LocalDirectory};
+use xsplot::svg::to_svg;
+
+/// Query the nuclide catalog and download cross sections without a browser.
+#[derive(Parser, Debug)]
+#[command(name = "xsplot", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Library registry to layer over the bundled one, ~/.config/xsplot/libraries.json when present
+    #[arg(long, global = true)]
+    libraries: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the evaluated libraries in the registry and where their files are read from
+    Libraries,
+    /// List the catalog entries matching the query
+    List {
+        #[command(flatten)]
+        query: Query,
+    },
+    /// Download the energy and cross section arrays matching the query
+    Fetch {
+        #[command(flatten)]
+        query: Query,
+        #[command(flatten)]
+        source: SourceArgs,
+        /// File to write to, stdout when omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// One of json, csv (a column per reaction), long-csv (a row per point), csv-zip (a file per reaction), endf (MF3 sections) or hdf5 (OpenMC layout)
+        #[arg(long, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+    /// Draw the cross sections matching the query as a figure styled like the web app's
+    Plot {
+        #[command(flatten)]
+        query: Query,
+        #[command(flatten)]
+        source: SourceArgs,
+        #[command(flatten)]
+        style: StyleArgs,
+        /// File to write to, stdout when omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// One of svg, html (an interactive plotly page) or python (a matplotlib script)
+        #[arg(long, default_value_t = FigureFormat::Svg)]
+        format: FigureFormat,
+    },
+    /// Regenerate the catalog from the libraries' JSON index files, or from the catalog CSV after editing it
+    BuildIndex {
+        /// Index files, or catalog CSVs, to read, by default the index file of every registered library in --index-dir
+        files: Vec<PathBuf>,
+        /// Directory holding the registered libraries' index files
+        #[arg(long, default_value = ".")]
+        index_dir: PathBuf,
+        /// Download the index files missing from --index-dir from their library's base URL
+        #[arg(long)]
+        download: bool,
+        /// Directory to write one compact catalog file per library and their manifest to
+        #[arg(long, default_value = "data/catalog")]
+        output_dir: PathBuf,
+        /// Where to write the whole catalog as one CSV, the text the compact files are generated from
+        #[arg(long, default_value = "data/catalog.csv")]
+        csv: PathBuf,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Query {
+    /// Element symbol, e.g. Fe
+    #[arg(short, long)]
+    element: Option<String>,
+    /// Number of nucleons, e.g. 56
+    #[arg(short, long)]
+    nucleons: Option<i32>,
+    /// MT reaction number, e.g. 102
+    #[arg(short, long)]
+    mt: Option<i32>,
+    /// Evaluated library, e.g. FENDL-3.2c
+    #[arg(short, long)]
+    library: Option<String>,
+    /// Incident particle, e.g. n, p or proton
+    #[arg(short, long)]
+    projectile: Option<String>,
+    /// Temperature in kelvin, e.g. 294 or 0K
+    #[arg(short, long)]
+    temperature: Option<String>,
+    /// Entry key as printed by `list`, e.g. Fe_56_FENDL-3.2c_n_102_294K, may be repeated
+    #[arg(short, long)]
+    key: Vec<EntryKey>,
+}
+
+#[derive(Args, Debug)]
+struct SourceArgs {
+    /// Read the JSON files from this mirror instead of the GitHub repositories
+    #[arg(long, conflicts_with = "data_dir")]
+    base_url: Option<String>,
+    /// Read the JSON files from this local directory, for offline use
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct StyleArgs {
+    /// Figure width in pixels
+    #[arg(long)]
+    width: Option<usize>,
+    /// Figure height in pixels
+    #[arg(long)]
+    height: Option<usize>,
+    /// Font family of the labels, as CSS gives it
+    #[arg(long)]
+    font_family: Option<String>,
+    /// Font size of the labels in pixels
+    #[arg(long)]
+    font_size: Option<usize>,
+    /// Width of the plotted lines in pixels
+    #[arg(long)]
+    line_width: Option<f64>,
+    /// Plot energies on a linear axis rather than a log one
+    #[arg(long)]
+    linear_x: bool,
+    /// Plot cross sections on a linear axis rather than a log one
+    #[arg(long)]
+    linear_y: bool,
+}
+
+impl StyleArgs {
+    fn style(&self) -> Result<FigureStyle, String> {
+        let default = FigureStyle::default();
+        let style = FigureStyle {
+            width: self.width.unwrap_or(default.width),
+            height: self.height.unwrap_or(default.height),
+            font_family: self.font_family.clone().unwrap_or(default.font_family),
+            font_size: self.font_size.unwrap_or(default.font_size),
+            line_width: self.line_width.unwrap_or(default.line_width),
+            ..default
+        };
+        style.validate()?;
+        Ok(style)
+    }
+}
+
+impl SourceArgs {
+    fn source(&self) -> Box<dyn DataSource> {
+        match (&self.base_url, &self.data_dir) {
+            (Some(base_url), _) => Box::new(BaseUrl::new(base_url)),
+            (None, Some(data_dir)) => Box::new(LocalDirectory::new(data_dir)),
+            (None, None) => Box::new(GitHub),
+        }
+    }
+}
+
+impl Query {
+    fn is_empty(&self) -> bool {
+        self.element.is_none() && self.nucleons.is_none() && self.mt.is_none() && self.library.is_none() && self.projectile.is_none() && self.temperature.is_none() && self.key.is_empty()
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let element_match = self.element.as_ref().is_none_or(|e| entry.element.eq_ignore_ascii_case(e));
+        let nucleons_match = self.nucleons.is_none_or(|n| entry.nucleons == n);
+        let mt_match = self.mt.is_none_or(|mt| entry.mt == mt);
+        let library_match = self.library.as_ref().is_none_or(|l| entry.library.eq_ignore_ascii_case(l));
+        let projectile_match = self.projectile.as_ref().is_none_or(|p| projectile_symbol(&entry.projectile) == projectile_symbol(p));
+        let temperature_match = self.temperature.as_ref().is_none_or(|t| &*entry.temperature == t.trim_end_matches(['K', 'k']));
+        let key_match = self.key.is_empty() || self.key.iter().any(|key| entry.has_key(key));
+
+        element_match && nucleons_match && mt_match && library_match && projectile_match && temperature_match && key_match
+    }
+}
+
+/// Where the user's library registry is read from when `--libraries` is not given.
+fn default_libraries_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("xsplot").join("libraries.json"))
+}
+
+/// Merges the user's libraries into the registry.
+fn load_libraries(path: Option<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let path = match path {
+        Some(path) => path,
+        None => match default_libraries_path().filter(|path| path.exists()) {
+            Some(path) => path,
+            None => return Ok(()),
+        },
+    };
+    let json = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut registry = Registry::default();
+    registry.merge(Registry::from_json(&json).map_err(|e| format!("{}: {}", path.display(), e))?);
+    set_registry(registry);
+    Ok(())
+}
+
+/// The embedded catalog with the reactions of the registered libraries it lacks.
+async fn load_catalog() -> Data {
+    let mut data = catalog().clone();
+    let (entries, failures) = fetch_missing_libraries(&data).await;
+    for (library, e) in &failures {
+        eprintln!("warning: the reactions of {} are not listed: {}", library, e);
+    }
+    if let Err(e) = data.extend(entries) {
+        eprintln!("warning: the reactions of the registered libraries are not listed: {}", e);
+    }
+    data
+}
+
+/// The index files `build-index` reads when none are named, downloading the missing ones if asked to.
+async fn registered_index_files(index_dir: &Path, download: bool) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for library in &registry().libraries {
+        let path = index_dir.join(&library.index_file);
+        if !path.exists() {
+            if !download {
+                return Err(format!("{} is missing, pass --download to fetch it from {}", path.display(), library.index_url()).into());
+            }
+            eprintln!("downloading {}", library.index_url());
+            fs::create_dir_all(index_dir)?;
+            let response = reqwest::get(library.index_url()).await?.error_for_status()?;
+            fs::write(&path, response.bytes().await?)?;
+        }
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// Reads the index files into one catalog, numbering the entries in file order.
+///
+/// Unregistered libraries, unknown incident particles and keys listed twice are
+/// errors, as their reactions could not be downloaded or told apart. Metastable
+/// states are numbered as they are read, see [`number_isomers`](xsplot::catalog::number_isomers),
+/// and a reaction listed twice for one state is rejected. Reactions the app can
+/// not name and temperatures a library does not list are only warned about.
+fn build_index(files: &[PathBuf]) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let registry = registry();
+    let reaction_name = get_reaction_name_map();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut seen: HashSet<EntryKey> = HashSet::new();
+    for file in files {
+        let contents = fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        let file_entries = if file.extension().is_some_and(|extension| extension == "csv") {
+            load_data_from_csv(&contents)
+        } else {
+            load_data_from_index(&contents, entries.len() as i32)
+        };
+        let file_entries = file_entries.map_err(|e| format!("{}: {}", file.display(), e))?;
+        for entry in file_entries {
+            let key = entry.key();
+            if !seen.insert(key.clone()) {
+                return Err(format!("{}: {} is listed twice", file.display(), key).into());
+            }
+            match registry.get(&entry.library) {
+                None => return Err(format!("{}: library {} of {} is not in the registry", file.display(), entry.library, key).into()),
+                Some(library) if !library.has_temperature(&entry.temperature) => {
+                    eprintln!("warning: {}: {} is at a temperature {} does not list", file.display(), key, library.name);
+                }
+                Some(_) => {}
+            }
+            if !reaction_name.contains_key(&entry.mt) {
+                eprintln!("warning: {}: {} has an MT number without a reaction name", file.display(), key);
+            }
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Writes one compact catalog file per library, in order of first appearance, and the manifest listing them.
+fn write_catalog(entries: &[Entry], output_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut manifest = Manifest::default();
+    for entry in entries {
+        if manifest.get(&entry.library).is_none() {
+            manifest.libraries.push(ManifestLibrary { name: entry.library.to_string(), file: format!("{}.bin", entry.library), entries: 0 });
+        }
+    }
+
+    fs::create_dir_all(output_dir)?;
+    for library in &mut manifest.libraries {
+        let library_entries: Vec<Entry> = entries.iter().filter(|entry| *entry.library == library.name).cloned().collect();
+        library.entries = library_entries.len();
+        fs::write(output_dir.join(&library.file), write_data_to_binary(&library_entries)?)?;
+    }
+    fs::write(output_dir.join("manifest.json"), serde_json::to_string_pretty(&manifest)? + "\n")?;
+    Ok(())
+}
+
+/// The keys of the catalog entries matching `query`, which must narrow the catalog down.
+fn select(data: &Data, query: &Query) -> Result<Vec<EntryKey>, Box<dyn Error>> {
+    if query.is_empty() {
+        return Err("refusing to fetch the whole catalog, narrow the query with at least one filter".into());
+    }
+    let selected: Vec<EntryKey> = data.iter().filter(|entry| query.matches(entry)).map(|entry| entry.key()).collect();
+    if selected.is_empty() {
+        return Err("no catalog entries match the query".into());
+    }
+    Ok(selected)
+}
+
+/// Writes to the file, or to stdout with text ended by a newline so the prompt starts on a line of its own.
+fn write_output(output: Option<PathBuf>, contents: &[u8], is_text: bool) -> io::Result<()> {
+    match output {
+        Some(path) => fs::write(path, contents),
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(contents)?;
+            if is_text && !contents.is_empty() && !contents.ends_with(b"\n") {
+                stdout.write_all(b"\n")?;
+            }
+            stdout.flush()
+        }
+    }
+}
+
+/// Writes the libraries as CSV, as `libraries` prints them.
+fn write_libraries<W: Write>(writer: W, libraries: &[Library]) -> csv::Result<()> {
+    let mut writer = csv::WriterBuilder::new().terminator(csv::Terminator::Any(b'\n')).from_writer(writer);
+    writer.write_record(["name", "display_name", "evaluation", "version", "temperatures", "base_url"])?;
+    for library in libraries {
+        let temperatures = library.temperatures.join(" ");
+        writer.write_record([library.name.as_str(), library.display_name(), &library.evaluation, &library.version, &temperatures, &library.base_url])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the entries as CSV, as `list` prints them.
+fn write_entries<'a, W: Write>(writer: W, entries: impl Iterator<Item = &'a Entry>) -> csv::Result<()> {
+    let mut writer = csv::WriterBuilder::new().terminator(csv::Terminator::Any(b'\n')).from_writer(writer);
+    writer.write_record(["key", "element", "nucleons", "isomer", "reaction", "mt", "library", "temperature"])?;
+    for entry in entries {
+        writer.write_record([
+            entry.key().to_string(),
+            entry.element.to_string(),
+            entry.nucleons.to_string(),
+            entry.isomer.to_string(),
+            entry.reaction.to_string(),
+            entry.mt.to_string(),
+            entry.library.to_string(),
+            entry.temperature.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints the reactions that could not be fetched, failing when there were any.
+fn report_failures(failures: &[(EntryKey, FetchError)], selected: usize) -> Result<(), Box<dyn Error>> {
+    for (key, e) in failures {
+        eprintln!("error: {}: {}", key, e);
+    }
+    if !failures.is_empty() {
+        return Err(format!("{} of {} reactions could not be fetched", failures.len(), selected).into());
+    }
+    Ok(())
+}
+
+pub async fn run() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    load_libraries(cli.libraries)?;
+
+    match cli.command {
+        Command::Libraries => write_libraries(io::stdout(), &registry().libraries)?,
+        Command::List { query } => {
+            let data = load_catalog().await;
+            write_entries(io::stdout(), data.iter().filter(|entry| query.matches(entry)))?
+        }
+        Command::Fetch { query, source, output, format } => {
+            let data = load_catalog().await;
+            let selected = select(&data, &query)?;
+            let (cache, failures) = generate_cache(&selected, &data, source.source().as_ref()).await;
+            write_output(output, &format.export(&cache)?, format.is_text())?;
+            report_failures(&failures, selected.len())?;
+        }
+        Command::Plot { query, source, style, output, format } => {
+            if format == FigureFormat::Png {
+                return Err("PNG figures are only drawn by the web app, save an SVG and convert it".into());
+            }
+            let figure_style = style.style()?;
+            let data = load_catalog().await;
+            let selected = select(&data, &query)?;
+            let (cache, failures) = generate_cache(&selected, &data, source.source().as_ref()).await;
+            let mut colours = ColourMap::default();
+            let colours: Vec<&str> = cache.keys.iter().enumerate().map(|(i, key)| colours.assign(key, &cache.keys[..i])).collect();
+            let plot = figure(&cache, &colours, !style.linear_x, !style.linear_y, &figure_style);
+            let contents = match format {
+                FigureFormat::Html => {
+                    let plotly_js = fetch_plotly_js().await;
+                    if let Err(e) = &plotly_js {
+                        eprintln!("warning: the page will load plotly.js from the internet: {}", e);
+                    }
+                    to_html(&plot, plotly_js.as_deref().ok())
+                }
+                FigureFormat::Python => to_matplotlib(&plot)?,
+                _ => to_svg(&plot)?,
+            };
+            write_output(output, contents.as_bytes(), true)?;
+            report_failures(&failures, selected.len())?;
+        }
+        Command::BuildIndex { files, index_dir, download, output_dir, csv } => {
+            let files = if files.is_empty() { registered_index_files(&index_dir, download).await? } else { files };
+            let entries = build_index(&files)?;
+            write_catalog(&entries, &output_dir)?;
+            fs::write(csv, write_data_to_csv(&entries))?;
+            eprintln!("{} reactions from {} files", entries.len(), files.len());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn names_with_commas_stay_one_field() {
+        let entry = Entry {
+            id: 0,
+            element: Arc::from("Fe"),
+            nucleons: 56,
+            library: Arc::from("FENDL-3.2c"),
+            projectile: Arc::from("n"),
+            reaction: Arc::from("(n,gamma)"),
+            mt: 102,
+            isomer: 0,
+            temperature: Arc::from("294"),
+        };
+        let mut output = Vec::new();
+        write_entries(&mut output, [entry].iter()).unwrap();
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        let rows: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].len(), 8);
+        assert_eq!(&rows[0][4], "(n,gamma)");
+
+        let library = Library {
+            name: "FENDL-3.2c".to_string(),
+            display_name: Some("FENDL 3.2c, fusion".to_string()),
+            evaluation: "FENDL".to_string(),
+            version: "3.2c".to_string(),
+            base_url: "https://example.org".to_string(),
+            url_template: None,
+            index_file: "index.json".to_string(),
+            temperatures: vec!["294".to_string(), "600".to_string()],
+        };
+        let mut output = Vec::new();
+        write_libraries(&mut output, &[library]).unwrap();
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        let rows: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(&rows[0][1], "FENDL 3.2c, fusion");
+        assert_eq!(&rows[0][4], "294 600");
+    }
+
+    /// Writes `contents` to a file of its own for one test.
+    fn test_file(name: &str, contents: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("xsplot-cli-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("index.json");
+        fs::write(&file, contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn particles_are_told_apart_by_their_full_name() {
+        let record = |particle: &str, mt: i32| {
+            format!(
+                r#"{{"Atomic symbol": "Fe", "Mass number": 56, "Incident particle": "{}", "MT reaction number": {}, "Library": "FENDL-3.2c", "Temperature(K)": "294"}}"#,
+                particle, mt
+            )
+        };
+        let index = format!("[{}, {}]", record("proton", 5), record("photon", 5));
+        let entries = build_index(&[test_file("particles", &index)]).unwrap();
+        let keys: Vec<String> = entries.iter().map(|entry| entry.key().to_string()).collect();
+        assert_eq!(keys, ["Fe_56_FENDL-3.2c_p_5_294K", "Fe_56_FENDL-3.2c_g_5_294K"]);
+        assert_eq!(&*entries[1].reaction, "(gamma,misc)");
+
+        let index = format!("[{}]", record("pion", 5));
+        let error = build_index(&[test_file("unknown_particle", &index)]).unwrap_err().to_string();
+        assert!(error.ends_with("Index record 0 has an unknown incident particle pion"), "{}", error);
+
+        let index = format!("[{}, {}, {}]", record("proton", 5), record("proton", 2), record("proton", 5));
+        let error = build_index(&[test_file("duplicate", &index)]).unwrap_err().to_string();
+        assert!(error.ends_with("Fe_56_FENDL-3.2c_p_5_294K is listed twice"), "{}", error);
+    }
+}
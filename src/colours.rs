@@ -0,0 +1,78 @@
+//! Line colours that stay with a reaction while others are plotted or removed.
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Plotly's default colour cycle.
+pub const PALETTE: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd",
+    "#8c564b", "#e377c2", "#7f7f7f", "#bcbd22", "#17becf",
+];
+
+/// Remembers which palette colour each reaction was given.
+///
+/// A reaction keeps its colour for as long as the map lives, unless another
+/// plotted reaction took it over while it was deselected.
+#[derive(Clone, Debug)]
+pub struct ColourMap<K> {
+    assigned: HashMap<K, usize>,
+}
+
+impl<K> Default for ColourMap<K> {
+    fn default() -> Self {
+        Self { assigned: HashMap::new() }
+    }
+}
+
+impl<K: Eq + Hash + Clone> ColourMap<K> {
+    /// The colour for `key`, avoiding the colours of the `plotted` reactions while the palette allows.
+    pub fn assign<'a>(&mut self, key: &K, plotted: impl IntoIterator<Item = &'a K>) -> &'static str
+    where
+        K: 'a,
+    {
+        let mut used = [0usize; PALETTE.len()];
+        for other in plotted.into_iter().filter(|other| *other != key) {
+            if let Some(&slot) = self.assigned.get(other) {
+                used[slot] += 1;
+            }
+        }
+
+        let slot = match self.assigned.get(key) {
+            Some(&slot) if used[slot] == 0 => slot,
+            // Least used colour, the first in palette order on ties
+            _ => (0..PALETTE.len()).min_by_key(|&slot| used[slot]).unwrap_or(0),
+        };
+        self.assigned.insert(key.clone(), slot);
+        PALETTE[slot]
+    }
+
+    /// The colour `key` was last given, if any.
+    pub fn get(&self, key: &K) -> Option<&'static str> {
+        self.assigned.get(key).map(|&slot| PALETTE[slot])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colours_stay_with_reactions_that_are_removed_and_added_again() {
+        let mut colours = ColourMap::default();
+        let plotted = ["Fe56 (n,gamma)", "Li6 (n,t)", "U235 (n,fission)"];
+        let assigned: Vec<&str> = plotted.iter().enumerate().map(|(i, key)| colours.assign(key, &plotted[..i])).collect();
+        assert_eq!(assigned, &PALETTE[..3]);
+
+        // Removing one leaves the others their colours, and it gets its own back when added again
+        let remaining = [plotted[0], plotted[2]];
+        assert_eq!(colours.assign(&plotted[0], &remaining), PALETTE[0]);
+        assert_eq!(colours.assign(&plotted[2], &remaining), PALETTE[2]);
+        assert_eq!(colours.assign(&plotted[1], &remaining), PALETTE[1]);
+        assert_eq!(colours.get(&plotted[1]), Some(PALETTE[1]));
+
+        // Unless a reaction added meanwhile took it over
+        let mut colours = ColourMap::default();
+        colours.assign(&"Li6 (n,t)", []);
+        assert_eq!(colours.assign(&"Fe56 (n,gamma)", []), PALETTE[0]);
+        assert_eq!(colours.assign(&"Li6 (n,t)", &["Fe56 (n,gamma)"]), PALETTE[1]);
+    }
+}
@@ -0,0 +1,243 @@
+//! Writing cross sections as ENDF-6 File 3 sections, for splicing a plotted,
+//! sliced or modified reaction back into an evaluation before processing it.
+//!
+//! Each reaction becomes an MF3 section, a HEAD record with the nuclide's ZA
+//! and mass followed by a TAB1 record of its points with linear-linear
+//! interpolation, which is how the pointwise files were reconstructed. Reactions
+//! of one nuclide, library and temperature are written as one material, in MT
+//! order, and the materials make up one tape. There is no File 1 directory, so
+//! a tape is meant to have its sections copied into the original evaluation
+//! rather than be processed on its own.
+//!
+//! The reaction files carry no Q values, so QM and QI are written as zero and
+//! need filling in from the evaluation for reactions whose processing uses them.
+use std::collections::BTreeMap;
+
+use crate::catalog::EntryKey;
+use crate::fetch::XsCache;
+use crate::nuclides::Nuclide;
+
+/// ENDF interpolation law for y linear in x.
+const LINEAR_LINEAR: i64 = 2;
+
+/// Width of one of the six fields of a line.
+const FIELD_WIDTH: usize = 11;
+
+/// An 11 column ENDF float, such as ` 1.234567+6` or `-2.50000-10`.
+///
+/// The exponent is written without the `E` so a seventh significant digit fits.
+pub fn format_float(value: f64) -> String {
+    let sign = if value < 0.0 { '-' } else { ' ' };
+    let mut digits = 6;
+    loop {
+        let formatted = format!("{:.*e}", digits, value.abs());
+        let (mantissa, exponent) = formatted.split_once('e').unwrap();
+        let exponent: i32 = exponent.parse().unwrap();
+        let exponent = format!("{}{}", if exponent < 0 { '-' } else { '+' }, exponent.abs());
+        if 1 + mantissa.len() + exponent.len() <= FIELD_WIDTH || digits == 0 {
+            return format!("{:>width$}", format!("{}{}{}", sign, mantissa, exponent), width = FIELD_WIDTH);
+        }
+        digits -= 1;
+    }
+}
+
+fn format_integer(value: i64) -> String {
+    format!("{:>width$}", value, width = FIELD_WIDTH)
+}
+
+/// Lines of an ENDF tape, numbered as they are written.
+struct Tape {
+    text: String,
+    /// -1 for the record ending the tape
+    mat: i32,
+    mf: u32,
+    mt: u32,
+    line: u32,
+}
+
+impl Tape {
+    fn new(description: &str) -> Self {
+        let mut tape = Self { text: String::new(), mat: 1, mf: 0, mt: 0, line: 0 };
+        let description: String = description.chars().take(FIELD_WIDTH * 6).collect();
+        tape.write(&description, 0);
+        tape
+    }
+
+    /// Writes 66 columns of data followed by the MAT, MF, MT and line number.
+    fn write(&mut self, data: &str, line: u32) {
+        self.text += &format!("{:<66}{:>4}{:>2}{:>3}{:>5}\n", data, self.mat, self.mf, self.mt, line);
+    }
+
+    fn line(&mut self, fields: &[String]) {
+        // Sections longer than the five digit line numbers carry on from 1
+        self.line = self.line % 99999 + 1;
+        self.write(&fields.concat(), self.line);
+    }
+
+    fn cont(&mut self, c1: f64, c2: f64, l1: i64, l2: i64, n1: i64, n2: i64) {
+        self.line(&[format_float(c1), format_float(c2), format_integer(l1), format_integer(l2), format_integer(n1), format_integer(n2)]);
+    }
+
+    /// A TAB1 record of `points` interpolated linearly throughout.
+    fn tab1(&mut self, c1: f64, c2: f64, l1: i64, l2: i64, points: &[(f64, f64)]) {
+        self.cont(c1, c2, l1, l2, 1, points.len() as i64);
+        self.line(&[format_integer(points.len() as i64), format_integer(LINEAR_LINEAR)]);
+        for chunk in points.chunks(3) {
+            let fields: Vec<String> = chunk.iter().flat_map(|(x, y)| [format_float(*x), format_float(*y)]).collect();
+            self.line(&fields);
+        }
+    }
+
+    /// Ends a section, a file, a material or the tape, whichever of MT, MF
+    /// and MAT have been set to zero.
+    fn end(&mut self, line: u32) {
+        let zeros = [format_float(0.0), format_float(0.0), format_integer(0), format_integer(0), format_integer(0), format_integer(0)];
+        self.write(&zeros.concat(), line);
+        self.line = 0;
+    }
+
+    fn section(&mut self, nuclide: &Nuclide, awr: f64, mt: u32, points: &[(f64, f64)]) {
+        self.mt = mt;
+        self.cont(nuclide.za() as f64, awr, 0, 0, 0, 0);
+        self.tab1(0.0, 0.0, 0, 0, points);
+        self.mt = 0;
+        self.end(99999);
+    }
+
+    fn material(&mut self, material: &Material) {
+        self.mat = material.mat as i32;
+        self.mf = 3;
+        for (mt, points) in &material.sections {
+            self.section(&material.nuclide, material.awr, *mt, points);
+        }
+        self.mf = 0;
+        self.end(0);
+        self.mat = 0;
+        self.end(0);
+    }
+
+    fn finish(mut self) -> String {
+        self.mat = -1;
+        self.end(0);
+        self.text
+    }
+}
+
+/// The reactions of one nuclide from one library at one temperature, by MT.
+struct Material<'a> {
+    mat: u32,
+    library: &'a str,
+    temperature: &'a str,
+    nuclide: Nuclide,
+    awr: f64,
+    sections: BTreeMap<u32, Vec<(f64, f64)>>,
+}
+
+/// The curves as an ENDF-6 tape of MF3 sections, failing for nuclides that
+/// have no ENDF material number or no tabulated mass.
+pub fn to_endf(cache: &XsCache) -> Result<String, String> {
+    // In order of first appearance
+    let mut materials: Vec<Material> = Vec::new();
+    for (i, key) in cache.keys.iter().enumerate() {
+        let EntryKey { element, nucleons, isomer, library, mt, temperature, .. } = key;
+        let nuclide = Nuclide::new(element, *nucleons).ok_or_else(|| format!("{} is not a known nuclide", key.nuclide()))?.in_state(*isomer);
+        let mat = nuclide.mat().ok_or_else(|| format!("{} has no ENDF material number", key.nuclide()))?;
+        let awr = nuclide.awr().ok_or_else(|| format!("{} has no tabulated atomic weight ratio", key.nuclide()))?;
+        let mt = u32::try_from(*mt).map_err(|_| format!("{} has no valid MT number", key))?;
+        let points: Vec<(f64, f64)> = cache.energy_values[i].iter().copied().zip(cache.cross_section_values[i].iter().copied()).collect();
+
+        match materials.iter_mut().find(|material| material.mat == mat && material.library == library && material.temperature == temperature) {
+            Some(material) => {
+                material.sections.insert(mt, points);
+            }
+            None => materials.push(Material { mat, library, temperature, nuclide, awr, sections: BTreeMap::from([(mt, points)]) }),
+        }
+    }
+
+    let mut libraries: Vec<&str> = Vec::new();
+    for material in &materials {
+        if !libraries.contains(&material.library) {
+            libraries.push(material.library);
+        }
+    }
+    let mut tape = Tape::new(&format!("{} cross sections exported by xsplot", libraries.join(", ")));
+    for material in &materials {
+        tape.material(material);
+    }
+    Ok(tape.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_fill_eleven_columns() {
+        assert_eq!(format_float(0.0), " 0.000000+0");
+        assert_eq!(format_float(2.0e7), " 2.000000+7");
+        assert_eq!(format_float(1.0e-5), " 1.000000-5");
+        assert_eq!(format_float(-0.5), "-5.000000-1");
+        assert_eq!(format_float(1.234_567_89e10), " 1.23457+10");
+        assert_eq!(format_float(9.999_999_9), " 1.000000+1");
+    }
+
+    #[test]
+    fn writes_a_material_per_nuclide_with_sections_in_mt_order() {
+        let keys: Vec<EntryKey> = ["Fe_56_FENDL-3.2c_n_102_294K", "Fe_56_FENDL-3.2c_n_2_294K"].map(|key| key.parse().unwrap()).into();
+        let cache = XsCache {
+            keys,
+            energy_values: vec![vec![1.0e-5, 2.0e7], vec![1.0e-5, 1.0, 2.0, 2.0e7]],
+            cross_section_values: vec![vec![2.6, 1.0e-4], vec![12.0, 11.5, 11.0, 0.9]],
+            checkbox_selected: vec![true, true],
+            labels: vec![String::new(), String::new()],
+        };
+        let tape = to_endf(&cache).unwrap();
+        let lines: Vec<&str> = tape.lines().collect();
+        assert!(lines.iter().all(|line| line.len() == 80));
+        assert_eq!(
+            lines,
+            [
+                "FENDL-3.2c cross sections exported by xsplot                         1 0  0    0",
+                " 2.605600+4 5.545443+1          0          0          0          02631 3  2    1",
+                " 0.000000+0 0.000000+0          0          0          1          42631 3  2    2",
+                "          4          2                                            2631 3  2    3",
+                " 1.000000-5 1.200000+1 1.000000+0 1.150000+1 2.000000+0 1.100000+12631 3  2    4",
+                " 2.000000+7 9.000000-1                                            2631 3  2    5",
+                " 0.000000+0 0.000000+0          0          0          0          02631 3  099999",
+                " 2.605600+4 5.545443+1          0          0          0          02631 3102    1",
+                " 0.000000+0 0.000000+0          0          0          1          22631 3102    2",
+                "          2          2                                            2631 3102    3",
+                " 1.000000-5 2.600000+0 2.000000+7 1.000000-4                      2631 3102    4",
+                " 0.000000+0 0.000000+0          0          0          0          02631 3  099999",
+                " 0.000000+0 0.000000+0          0          0          0          02631 0  0    0",
+                " 0.000000+0 0.000000+0          0          0          0          0   0 0  0    0",
+                " 0.000000+0 0.000000+0          0          0          0          0  -1 0  0    0",
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_nuclides_without_a_tabulated_mass() {
+        let cache = XsCache {
+            keys: vec!["Fe_54_FENDL-3.2c_n_102_294K".parse().unwrap()],
+            energy_values: vec![vec![1.0e-5, 2.0e7]],
+            cross_section_values: vec![vec![2.6, 1.0e-4]],
+            checkbox_selected: vec![true],
+            labels: vec![String::new()],
+        };
+        assert_eq!(to_endf(&cache), Err("Fe54 has no tabulated atomic weight ratio".to_string()));
+    }
+
+    #[test]
+    fn numbers_materials_like_endf_b() {
+        let mat = |element: &str, nucleons: i32| Nuclide::new(element, nucleons).and_then(|nuclide| nuclide.mat());
+        assert_eq!(mat("H", 1), Some(125));
+        assert_eq!(mat("Fe", 56), Some(2631));
+        assert_eq!(mat("U", 235), Some(9228));
+        assert_eq!(mat("Pu", 239), Some(9437));
+        assert_eq!(mat("Es", 253), Some(9913));
+        assert_eq!(Nuclide::new("Am", 242).and_then(|nuclide| nuclide.in_state(1).mat()), Some(9547));
+        assert_eq!(mat("At", 210), None);
+        assert_eq!(mat("Xx", 1), None);
+    }
+}
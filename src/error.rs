@@ -0,0 +1,38 @@
+//! Errors raised while reading reaction data.
+use std::error::Error;
+use std::fmt;
+
+/// Why a reaction could not be read.
+///
+/// Only strings are kept so failures can be stored in UI state and shown next
+/// to the row they belong to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FetchError {
+    /// No catalog entry has this key.
+    EntryNotFound(String),
+    /// The data source does not serve this library.
+    UnsupportedLibrary(String),
+    /// The server answered with an error status, such as 404 for a missing file.
+    Status { url: String, status: u16 },
+    /// The request failed before a response arrived.
+    Network(String),
+    /// A local file could not be read.
+    Io(String),
+    /// The file is not valid reaction JSON.
+    Parse(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::EntryNotFound(key) => write!(f, "Entry {} not found in the catalog", key),
+            FetchError::UnsupportedLibrary(library) => write!(f, "Unsupported library: {}", library),
+            FetchError::Status { url, status } => write!(f, "{} returned HTTP {}", url, status),
+            FetchError::Network(message) => write!(f, "Network error: {}", message),
+            FetchError::Io(message) => write!(f, "Could not read file: {}", message),
+            FetchError::Parse(message) => write!(f, "Invalid reaction data: {}", message),
+        }
+    }
+}
+
+impl Error for FetchError {}
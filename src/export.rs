@@ -0,0 +1,247 @@
+//! Writing downloaded curves out in the formats offered for download.
+//!
+//! Everything here works on an [`XsCache`] so the browser and the command line
+//! export the same files.
+use std::fmt;
+use std::io::Write as _;
+use std::str::FromStr;
+
+use crate::endf::to_endf;
+use crate::fetch::XsCache;
+
+/// A file format the selected curves can be saved in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The curves as parallel arrays, as the app has always offered
+    #[default]
+    Json,
+    /// One energy column and one cross section column per reaction
+    WideCsv,
+    /// One row per point, labelled with the reaction it belongs to
+    LongCsv,
+    /// A zip archive with a two column CSV per reaction
+    CsvZip,
+    /// ENDF-6 File 3 sections, a material per nuclide, library and temperature
+    Endf,
+    /// HDF5 laid out like OpenMC's incident neutron data, written by the command line only
+    Hdf5,
+}
+
+impl ExportFormat {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const ALL: &'static [ExportFormat] =
+        &[ExportFormat::Json, ExportFormat::WideCsv, ExportFormat::LongCsv, ExportFormat::CsvZip, ExportFormat::Endf, ExportFormat::Hdf5];
+    /// The formats the browser offers.
+    #[cfg(target_arch = "wasm32")]
+    pub const ALL: &'static [ExportFormat] = &[ExportFormat::Json, ExportFormat::WideCsv, ExportFormat::LongCsv, ExportFormat::CsvZip, ExportFormat::Endf];
+
+    /// Name shown in the download menu.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::WideCsv => "CSV, a column per reaction",
+            ExportFormat::LongCsv => "CSV, a row per point",
+            ExportFormat::CsvZip => "Zip of CSVs, a file per reaction",
+            ExportFormat::Endf => "ENDF-6, MF3 sections",
+            ExportFormat::Hdf5 => "HDF5, OpenMC layout",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::WideCsv | ExportFormat::LongCsv => "csv",
+            ExportFormat::CsvZip => "zip",
+            ExportFormat::Endf => "endf",
+            ExportFormat::Hdf5 => "h5",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::WideCsv | ExportFormat::LongCsv => "text/csv",
+            ExportFormat::CsvZip => "application/zip",
+            ExportFormat::Endf => "text/plain",
+            ExportFormat::Hdf5 => "application/x-hdf5",
+        }
+    }
+
+    /// Whether the file is text, which a terminal can show, rather than a binary archive.
+    pub fn is_text(&self) -> bool {
+        !matches!(self, ExportFormat::CsvZip | ExportFormat::Hdf5)
+    }
+
+    /// Name the download is saved under.
+    pub fn file_name(&self) -> String {
+        match self {
+            ExportFormat::LongCsv => "cross_sections_from_xsplot_long.csv".to_string(),
+            _ => format!("cross_sections_from_xsplot.{}", self.extension()),
+        }
+    }
+
+    pub fn export(&self, cache: &XsCache) -> Result<Vec<u8>, String> {
+        match self {
+            ExportFormat::Json => cache.to_json().map(String::into_bytes).map_err(|e| e.to_string()),
+            ExportFormat::WideCsv => Ok(to_wide_csv(cache).into_bytes()),
+            ExportFormat::LongCsv => Ok(to_long_csv(cache).into_bytes()),
+            ExportFormat::CsvZip => to_csv_zip(cache),
+            ExportFormat::Endf => to_endf(cache).map(String::into_bytes),
+            #[cfg(not(target_arch = "wasm32"))]
+            ExportFormat::Hdf5 => crate::openmc::to_hdf5(cache),
+            #[cfg(target_arch = "wasm32")]
+            ExportFormat::Hdf5 => Err("HDF5 files are only written by the command line".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Json => "json",
+            ExportFormat::WideCsv => "csv",
+            ExportFormat::LongCsv => "long-csv",
+            ExportFormat::CsvZip => "csv-zip",
+            ExportFormat::Endf => "endf",
+            ExportFormat::Hdf5 => "hdf5",
+        })
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().copied().find(|format| format.to_string() == s).ok_or_else(|| {
+            let formats: Vec<String> = Self::ALL.iter().map(|format| format.to_string()).collect();
+            format!("unknown format {}, expected one of {}", s, formats.join(", "))
+        })
+    }
+}
+
+fn csv_writer() -> csv::Writer<Vec<u8>> {
+    csv::WriterBuilder::new().terminator(csv::Terminator::Any(b'\n')).from_writer(Vec::new())
+}
+
+/// Writing to memory cannot fail, so neither can finishing it.
+fn finish(writer: csv::Writer<Vec<u8>>) -> String {
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+/// Linearly interpolates the curve at `energy`, `None` outside of it.
+///
+/// The files are pointwise data reconstructed for linear interpolation, so
+/// this puts every reaction on one energy grid without changing its shape.
+pub(crate) fn interpolate(energy_values: &[f64], cross_section_values: &[f64], energy: f64) -> Option<f64> {
+    let after = energy_values.partition_point(|value| *value < energy);
+    let (&high_energy, &high) = (energy_values.get(after)?, cross_section_values.get(after)?);
+    if high_energy == energy {
+        return Some(high);
+    }
+    let (&low_energy, &low) = (energy_values.get(after.checked_sub(1)?)?, cross_section_values.get(after - 1)?);
+    Some(low + (high - low) * (energy - low_energy) / (high_energy - low_energy))
+}
+
+/// One row per energy of any reaction, with a cross section column per reaction
+/// headed by its label.
+///
+/// Reactions are interpolated onto the energies of the others and left blank
+/// outside their own energy range.
+pub fn to_wide_csv(cache: &XsCache) -> String {
+    let mut energies: Vec<f64> = cache.energy_values.iter().flatten().copied().collect();
+    energies.sort_by(f64::total_cmp);
+    energies.dedup();
+
+    let mut writer = csv_writer();
+    let header = std::iter::once("energy [eV]").chain(cache.labels.iter().map(String::as_str));
+    writer.write_record(header).unwrap();
+    for energy in energies {
+        let mut row = vec![energy.to_string()];
+        for (energy_values, cross_section_values) in cache.energy_values.iter().zip(&cache.cross_section_values) {
+            row.push(interpolate(energy_values, cross_section_values, energy).map(|value| value.to_string()).unwrap_or_default());
+        }
+        writer.write_record(&row).unwrap();
+    }
+    finish(writer)
+}
+
+/// One row per point of every reaction, with the reaction's label and catalog
+/// fields repeated so it can be filtered or pivoted in a spreadsheet.
+pub fn to_long_csv(cache: &XsCache) -> String {
+    let mut writer = csv_writer();
+    writer
+        .write_record(["label", "element", "nucleons", "library", "projectile", "mt", "temperature", "energy [eV]", "cross section"])
+        .unwrap();
+    for (i, label) in cache.labels.iter().enumerate() {
+        let key = &cache.keys[i];
+        let (nucleons, mt) = (key.nucleons.to_string(), key.mt.to_string());
+        for (energy, cross_section) in cache.energy_values[i].iter().zip(&cache.cross_section_values[i]) {
+            let (energy, cross_section) = (energy.to_string(), cross_section.to_string());
+            writer
+                .write_record([label, &key.element, &nucleons, &key.library, &key.projectile, &mt, &key.temperature, &energy, &cross_section])
+                .unwrap();
+        }
+    }
+    finish(writer)
+}
+
+/// One reaction's points as a two column CSV.
+fn to_reaction_csv(energy_values: &[f64], cross_section_values: &[f64]) -> String {
+    let mut writer = csv_writer();
+    writer.write_record(["energy [eV]", "cross section"]).unwrap();
+    for (energy, cross_section) in energy_values.iter().zip(cross_section_values) {
+        writer.write_record([energy.to_string(), cross_section.to_string()]).unwrap();
+    }
+    finish(writer)
+}
+
+/// A zip archive of one CSV per reaction, named by its key such as
+/// `Fe_56_FENDL-3.2c_n_102_294K.csv`.
+pub fn to_csv_zip(cache: &XsCache) -> Result<Vec<u8>, String> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (i, key) in cache.keys.iter().enumerate() {
+        zip.start_file(format!("{}.csv", key), options).map_err(|e| e.to_string())?;
+        zip.write_all(to_reaction_csv(&cache.energy_values[i], &cache.cross_section_values[i]).as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(zip.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::EntryKey;
+
+    fn cache() -> XsCache {
+        let keys: Vec<EntryKey> = ["Fe_56_FENDL-3.2c_n_102_294K", "Li_6_FENDL-3.2c_n_105_294K"].map(|key| key.parse().unwrap()).into();
+        XsCache {
+            keys,
+            energy_values: vec![vec![1.0, 3.0], vec![2.0, 4.0]],
+            cross_section_values: vec![vec![10.0, 30.0], vec![5.0, 7.0]],
+            checkbox_selected: vec![true, true],
+            labels: vec!["Fe56 (n,gamma) FENDL-3.2c 294K".to_string(), "Li6 (n,t) FENDL-3.2c 294K".to_string()],
+        }
+    }
+
+    #[test]
+    fn wide_csv_interpolates_onto_every_energy() {
+        assert_eq!(
+            to_wide_csv(&cache()),
+            "energy [eV],\"Fe56 (n,gamma) FENDL-3.2c 294K\",\"Li6 (n,t) FENDL-3.2c 294K\"
+1,10,
+2,20,5
+3,30,6
+4,,7
+"
+        );
+    }
+
+    #[test]
+    fn long_csv_labels_every_point() {
+        let csv = to_long_csv(&cache());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[3], "\"Li6 (n,t) FENDL-3.2c 294K\",Li,6,FENDL-3.2c,n,105,294,2,5");
+    }
+}
@@ -0,0 +1,198 @@
+//! Reading catalog entries from a [`DataSource`] into plottable curves.
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::catalog::{load_data_from_binary, load_data_from_index, manifest, Data, Entry, EntryKey, LegacyIds, ManifestLibrary};
+use crate::error::FetchError;
+use crate::figure::PLOTLY_JS_URL;
+use crate::library::registry;
+use crate::source::DataSource;
+
+/// The contents of one reaction JSON file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReactionData {
+    #[serde(rename = "energy")]
+    pub energy_values: Vec<f64>,
+    #[serde(rename = "cross section")]
+    pub cross_section_values: Vec<f64>,
+}
+
+/// Downloaded curves for a selection, stored as parallel arrays.
+#[derive(PartialEq, Clone, Serialize)]
+pub struct XsCache {
+    /// Which entry each curve is, left out of the JSON download
+    #[serde(skip)]
+    pub keys: Vec<EntryKey>,
+    pub energy_values: Vec<Vec<f64>>,
+    pub cross_section_values: Vec<Vec<f64>>,
+    pub checkbox_selected: Vec<bool>,
+    pub labels: Vec<String>,
+}
+
+impl XsCache {
+    /// Pretty-printed JSON of the cached curves, as offered for download.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let mut json_value = serde_json::to_value(self)?;
+
+        // The checkbox state is UI bookkeeping, not data
+        if let Value::Object(ref mut map) = json_value {
+            map.remove("checkbox_selected");
+        }
+
+        serde_json::to_string_pretty(&json_value)
+    }
+}
+
+/// How many files are downloaded at once, browsers allow six connections per host.
+pub const MAX_CONCURRENT_FETCHES: usize = 6;
+
+/// Reads the entries of `data` with the selected keys from `source` into an [`XsCache`], several at a time.
+///
+/// The cache keeps the order of `selected`. Entries that could not be read are
+/// left out of it and returned alongside it.
+pub async fn generate_cache(selected: &[EntryKey], data: &Data, source: &dyn DataSource) -> (XsCache, Vec<(EntryKey, FetchError)>) {
+    let mut cache_keys = Vec::new();
+    let mut cache_energy_values = Vec::new();
+    let mut cache_cross_section_values = Vec::new();
+    let mut cache_checkbox_selected = Vec::new();
+    let mut cache_labels = Vec::new();
+    let mut failures = Vec::new();
+    let results: Vec<_> = stream::iter(selected)
+        .map(|selected_key| async move { (selected_key, get_values_by_key(selected_key, data, source).await) })
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+    for (selected_key, result) in results {
+        let (energy, cross_section, label) = match result {
+            Ok(values) => values,
+            Err(e) => {
+                failures.push((selected_key.clone(), e));
+                continue;
+            }
+        };
+        cache_keys.push(selected_key.clone());
+        cache_energy_values.push(energy);
+        cache_cross_section_values.push(cross_section);
+        cache_checkbox_selected.push(true);
+        cache_labels.push(label);
+        debug!("fetched {}", selected_key);
+    }
+
+    let cache = XsCache {
+        keys: cache_keys,
+        energy_values: cache_energy_values,
+        cross_section_values: cache_cross_section_values,
+        checkbox_selected: cache_checkbox_selected,
+        labels: cache_labels,
+    };
+    (cache, failures)
+}
+
+/// Reads the entry of `data`, the catalog as loaded, with the given key from `source`,
+/// returning energies, cross sections and a plot label.
+pub async fn get_values_by_key(key: &EntryKey, data: &Data, source: &dyn DataSource) -> Result<(Vec<f64>, Vec<f64>, String), FetchError> {
+    let entry = data.get_by_key(key).ok_or_else(|| FetchError::EntryNotFound(key.to_string()))?;
+    let downloaded_reaction_data = source.fetch(entry).await?;
+
+    Ok((downloaded_reaction_data.energy_values, downloaded_reaction_data.cross_section_values, get_label(entry)))
+}
+
+/// Reads the index of every registered library that has no catalog file and that `data` has no
+/// entries of, such as one added by the user, returning their entries and the libraries whose
+/// index could not be read.
+pub async fn fetch_missing_libraries(data: &Data) -> (Vec<Entry>, Vec<(String, FetchError)>) {
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    let registry = registry();
+    for library in registry.libraries.iter().filter(|library| manifest().get(&library.name).is_none() && !data.has_library(&library.name)) {
+        let url = library.index_url();
+        debug!("downloading index {}", url);
+        let index = match fetch_text(&url).await {
+            Ok(index) => index,
+            Err(e) => {
+                failures.push((library.name.clone(), e));
+                continue;
+            }
+        };
+        match load_data_from_index(&index, data.next_id() + entries.len() as i32) {
+            Ok(library_entries) => entries.extend(library_entries),
+            Err(e) => failures.push((library.name.clone(), FetchError::Parse(format!("{}: {}", url, e)))),
+        }
+    }
+    (entries, failures)
+}
+
+/// Downloads one library's catalog file from the directory `catalog_url` serves `data/catalog` at.
+pub async fn fetch_library_catalog(catalog_url: &str, library: &ManifestLibrary) -> Result<Vec<Entry>, FetchError> {
+    let url = format!("{}/{}", catalog_url.trim_end_matches('/'), library.file);
+    debug!("downloading catalog {}", url);
+    let bytes = fetch_bytes(&url).await?;
+    load_data_from_binary(&bytes, 0).map_err(|e| FetchError::Parse(format!("{}: {}", url, e)))
+}
+
+/// Downloads the table old links' numeric ids are looked up in, `data/legacy_ids.bin` served at `url`.
+pub async fn fetch_legacy_ids(url: &str) -> Result<LegacyIds, FetchError> {
+    debug!("downloading legacy ids {}", url);
+    let bytes = fetch_bytes(url).await?;
+    LegacyIds::from_binary(&bytes).map_err(|e| FetchError::Parse(format!("{}: {}", url, e)))
+}
+
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, FetchError> {
+    let response = reqwest::get(url).await.map_err(|e| FetchError::Network(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(FetchError::Status { url: url.to_string(), status: response.status().as_u16() });
+    }
+    response.bytes().await.map(|bytes| bytes.to_vec()).map_err(|e| FetchError::Network(e.to_string()))
+}
+
+async fn fetch_text(url: &str) -> Result<String, FetchError> {
+    let response = reqwest::get(url).await.map_err(|e| FetchError::Network(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(FetchError::Status { url: url.to_string(), status: response.status().as_u16() });
+    }
+    response.text().await.map_err(|e| FetchError::Network(e.to_string()))
+}
+
+/// Downloads plotly.js, for writing into saved web pages.
+pub async fn fetch_plotly_js() -> Result<String, FetchError> {
+    fetch_text(PLOTLY_JS_URL).await
+}
+
+/// The legend label used for an entry, e.g. `Fe56 (n,gamma) FENDL-3.2c 294K`, with the library's display name.
+pub fn get_label(entry: &Entry) -> String {
+    let registry = registry();
+    let library = registry.get(&entry.library).map_or(&*entry.library, |library| library.display_name());
+    format!("{} {} {} {}K", entry.key().nuclide(), entry.reaction, library, entry.temperature)
+}
+
+/// The file stem an entry is stored under, e.g. `Fe_56_FENDL-3.2c_n_102_294K`.
+///
+/// Ground states have the `{element}_{nucleons}_{library}_{projectile}_{mt}_{temperature}K`
+/// names of the openmc-data-storage repositories. Their indices give no
+/// metastable state, so neither can their file names, and metastable states
+/// have it after the nucleons, e.g. `Am_242m1_ENDFB-8.0_n_102_294K`, for
+/// mirrors and local copies that hold their files apart. The stems are the
+/// text form of [`EntryKey`] as it stands, but are built here as the files are
+/// named by the repositories rather than by the keys.
+pub fn convert_string(entry: &Entry) -> String {
+    let isomer = match entry.isomer {
+        0 => String::new(),
+        isomer => format!("m{}", isomer),
+    };
+    format!("{}_{}{}_{}_{}_{}_{}K", entry.element, entry.nucleons, isomer, entry.library, entry.projectile, entry.mt, entry.temperature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_stems_follow_the_repositories_names() {
+        let entry = Entry::from_key(&"Fe_56_FENDL-3.2c_n_102_294K".parse::<EntryKey>().unwrap());
+        assert_eq!(convert_string(&entry), "Fe_56_FENDL-3.2c_n_102_294K");
+        let entry = Entry { isomer: 1, ..Entry::from_key(&"Am_242_ENDFB-8.0_n_18_294K".parse::<EntryKey>().unwrap()) };
+        assert_eq!(convert_string(&entry), "Am_242m1_ENDFB-8.0_n_18_294K");
+    }
+}
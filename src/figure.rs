@@ -0,0 +1,325 @@
+//! The plotly figure of a selection, as drawn in the browser and saved as an
+//! image, a web page or a script.
+//!
+//! The web app and the command line build their figures here so a saved
+//! figure looks like the plot on screen, with the styling chosen for it on top.
+use std::fmt;
+use std::str::FromStr;
+
+use plotly::common::{Font, Line};
+use plotly::layout::{Axis, AxisType};
+use plotly::{Layout, Plot, Scatter};
+use serde_json::Value;
+
+use crate::fetch::XsCache;
+
+/// Where plotly.js is loaded from, by the web app and the web pages it saves.
+pub const PLOTLY_JS_URL: &str = "https://cdn.plot.ly/plotly-2.14.0.min.js";
+
+/// File a figure can be saved as.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FigureFormat {
+    #[default]
+    Svg,
+    /// Drawn by plotly.js, so only saved by the browser
+    Png,
+    /// A web page showing the interactive plotly figure
+    Html,
+    /// A matplotlib script drawing the curves, to restyle them in Python
+    Python,
+}
+
+impl FigureFormat {
+    pub const ALL: [FigureFormat; 4] = [FigureFormat::Svg, FigureFormat::Png, FigureFormat::Html, FigureFormat::Python];
+
+    /// Name shown in the download menu.
+    pub fn description(&self) -> &'static str {
+        match self {
+            FigureFormat::Svg => "SVG",
+            FigureFormat::Png => "PNG",
+            FigureFormat::Html => "HTML, interactive",
+            FigureFormat::Python => "Python, matplotlib",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FigureFormat::Svg => "svg",
+            FigureFormat::Png => "png",
+            FigureFormat::Html => "html",
+            FigureFormat::Python => "py",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FigureFormat::Svg => "image/svg+xml",
+            FigureFormat::Png => "image/png",
+            FigureFormat::Html => "text/html",
+            FigureFormat::Python => "text/x-python",
+        }
+    }
+
+    /// Name the download is saved under.
+    pub fn file_name(&self) -> String {
+        format!("cross_sections_from_xsplot.{}", self.extension())
+    }
+}
+
+impl fmt::Display for FigureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FigureFormat::Svg => "svg",
+            FigureFormat::Png => "png",
+            FigureFormat::Html => "html",
+            FigureFormat::Python => "python",
+        })
+    }
+}
+
+impl FromStr for FigureFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s)
+            .ok_or_else(|| format!("unknown figure format {}, expected svg, png, html or python", s))
+    }
+}
+
+/// How a saved figure is drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FigureStyle {
+    pub format: FigureFormat,
+    /// In CSS pixels, 1/96 inch
+    pub width: usize,
+    pub height: usize,
+    /// Resolution of PNG images, which are `width * dpi / 96` pixels wide
+    pub dpi: usize,
+    pub font_family: String,
+    /// In pixels, as plotly sizes fonts
+    pub font_size: usize,
+    pub line_width: f64,
+}
+
+impl Default for FigureStyle {
+    fn default() -> Self {
+        Self {
+            format: FigureFormat::default(),
+            width: 900,
+            height: 600,
+            dpi: 96,
+            font_family: "Arial, sans-serif".to_string(),
+            font_size: 14,
+            line_width: 2.0,
+        }
+    }
+}
+
+impl FigureStyle {
+    /// How many image pixels a CSS pixel becomes.
+    pub fn scale(&self) -> f64 {
+        self.dpi as f64 / 96.0
+    }
+
+    /// Checks every size is positive, as a figure cannot be drawn otherwise.
+    pub fn validate(&self) -> Result<(), String> {
+        let sizes = [("width", self.width), ("height", self.height), ("resolution", self.dpi), ("font size", self.font_size)];
+        if let Some((name, _)) = sizes.iter().find(|(_, size)| *size == 0) {
+            return Err(format!("the figure {} must be positive", name));
+        }
+        if !(self.line_width.is_finite() && self.line_width > 0.0) {
+            return Err(format!("the figure line width must be positive, not {}", self.line_width));
+        }
+        Ok(())
+    }
+}
+
+/// The y axis title for the plotted curves, which mixes units when heating
+/// or damage is plotted alongside cross sections.
+pub fn y_axis_title<S: AsRef<str>>(labels: &[S]) -> &'static str {
+    let is_heat_or_damage = |label: &S| label.as_ref().contains("heat") || label.as_ref().contains("damage");
+    let heat_or_damage_plotted = labels.iter().any(is_heat_or_damage);
+    let cross_section_plotted = labels.iter().any(|label| !is_heat_or_damage(label));
+
+    if cross_section_plotted && heat_or_damage_plotted {
+        "Microscopic Cross Section [barns], Heating Cross Section [eV-barn]"
+    } else if cross_section_plotted {
+        "Microscopic Cross Section [barns]"
+    } else if heat_or_damage_plotted {
+        "Heating Cross Section [eV-barn]"
+    } else {
+        "" // no data plotted
+    }
+}
+
+/// The axes of a plot of curves with the given labels.
+pub fn layout<S: AsRef<str>>(labels: &[S], is_x_log: bool, is_y_log: bool) -> Layout {
+    let y_axis = Axis::new()
+        .title(y_axis_title(labels))
+        .zero_line(true)
+        .type_(if is_y_log { AxisType::Log } else { AxisType::Linear });
+
+    let x_axis = Axis::new()
+        .title("Energy [eV]")
+        .zero_line(true)
+        .type_(if is_x_log { AxisType::Log } else { AxisType::Linear });
+
+    Layout::new().show_legend(true).x_axis(x_axis).y_axis(y_axis)
+}
+
+/// The curves of `cache` drawn in `colours`, one per curve, and styled for saving.
+pub fn figure(cache: &XsCache, colours: &[&'static str], is_x_log: bool, is_y_log: bool, style: &FigureStyle) -> Plot {
+    let mut plot = Plot::new();
+    for (i, label) in cache.labels.iter().enumerate() {
+        let trace = Scatter::new(cache.energy_values[i].clone(), cache.cross_section_values[i].clone())
+            .name(label)
+            .line(Line::new().color(colours[i]).width(style.line_width));
+        plot.add_trace(trace);
+    }
+    plot.set_layout(
+        layout(&cache.labels, is_x_log, is_y_log)
+            .width(style.width)
+            .height(style.height)
+            .font(Font::new().family(&style.font_family).size(style.font_size)),
+    );
+    plot
+}
+
+/// A web page showing the figure, with plotly.js written into it when given
+/// so it opens without internet access, and loaded from [`PLOTLY_JS_URL`] otherwise.
+pub fn to_html(plot: &Plot, plotly_js: Option<&str>) -> String {
+    // Neither may end the script element they are written into
+    let script = match plotly_js {
+        Some(plotly_js) => format!("<script>{}</script>", plotly_js.replace("</script", "<\\/script")),
+        None => format!("<script src=\"{}\"></script>", PLOTLY_JS_URL),
+    };
+    let figure = plot.to_json().replace("</", "<\\/");
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+    <meta charset=\"utf-8\"/>
+    <title>Cross sections plotted with xsplot</title>
+    {script}
+</head>
+<body>
+    <div id=\"plot\"></div>
+    <script>
+        const figure = {figure};
+        Plotly.newPlot(\"plot\", figure.data, figure.layout, {{responsive: true}});
+    </script>
+</body>
+</html>
+"
+    )
+}
+
+/// Plotly's defaults for a figure that leaves them out.
+const DEFAULT_WIDTH: f64 = 700.0;
+const DEFAULT_HEIGHT: f64 = 450.0;
+const DEFAULT_FONT_FAMILY: &str = "\"Open Sans\", verdana, arial, sans-serif";
+const DEFAULT_FONT_SIZE: f64 = 12.0;
+const DEFAULT_LINE_WIDTH: f64 = 2.0;
+const DEFAULT_COLOUR: &str = "#1f77b4";
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AxisData {
+    pub title: String,
+    pub is_log: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TraceData {
+    pub name: String,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub colour: String,
+    pub line_width: f64,
+}
+
+/// What a figure shows, read back from its plotly JSON for drawing it without plotly.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FigureData {
+    pub width: f64,
+    pub height: f64,
+    pub font_family: String,
+    pub font_size: f64,
+    pub x_axis: AxisData,
+    pub y_axis: AxisData,
+    pub traces: Vec<TraceData>,
+}
+
+fn values(value: &Value) -> Vec<f64> {
+    value.as_array().map(|values| values.iter().map(|value| value.as_f64().unwrap_or(f64::NAN)).collect()).unwrap_or_default()
+}
+
+fn axis_data(axis: &Value) -> AxisData {
+    AxisData {
+        title: axis["title"]["text"].as_str().or(axis["title"].as_str()).unwrap_or_default().to_string(),
+        is_log: axis["type"] == "log",
+    }
+}
+
+impl FigureData {
+    pub(crate) fn from_plot(plot: &Plot) -> Result<Self, String> {
+        let figure: Value = serde_json::from_str(&plot.to_json()).map_err(|e| e.to_string())?;
+        let layout = &figure["layout"];
+        let traces = figure["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|trace| TraceData {
+                name: trace["name"].as_str().unwrap_or_default().to_string(),
+                x: values(&trace["x"]),
+                y: values(&trace["y"]),
+                colour: trace["line"]["color"].as_str().unwrap_or(DEFAULT_COLOUR).to_string(),
+                line_width: trace["line"]["width"].as_f64().unwrap_or(DEFAULT_LINE_WIDTH),
+            })
+            .collect();
+        Ok(Self {
+            width: layout["width"].as_f64().unwrap_or(DEFAULT_WIDTH),
+            height: layout["height"].as_f64().unwrap_or(DEFAULT_HEIGHT),
+            font_family: layout["font"]["family"].as_str().unwrap_or(DEFAULT_FONT_FAMILY).to_string(),
+            font_size: layout["font"]["size"].as_f64().unwrap_or(DEFAULT_FONT_SIZE),
+            x_axis: axis_data(&layout["xaxis"]),
+            y_axis: axis_data(&layout["yaxis"]),
+            traces,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_keeps_labels_from_closing_the_script() {
+        let cache = XsCache {
+            keys: Vec::new(),
+            energy_values: vec![vec![1.0, 2.0]],
+            cross_section_values: vec![vec![3.0, 4.0]],
+            checkbox_selected: vec![true],
+            labels: vec!["</script><b>heating</b>".to_string()],
+        };
+        let plot = figure(&cache, &["#1f77b4"], true, true, &FigureStyle::default());
+        let html = to_html(&plot, Some("var Plotly = {}; // </script>"));
+
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert!(html.contains("<script>var Plotly = {}; // <\\/script></script>"));
+        assert!(html.contains("\"name\":\"<\\/script><b>heating<\\/b>\""));
+        assert!(html.contains("Heating Cross Section [eV-barn]"));
+    }
+
+    #[test]
+    fn styles_without_a_positive_size_are_rejected() {
+        assert_eq!(FigureStyle::default().validate(), Ok(()));
+        assert!(FigureStyle { font_size: 0, ..FigureStyle::default() }.validate().is_err());
+        assert!(FigureStyle { width: 0, ..FigureStyle::default() }.validate().is_err());
+        assert!(FigureStyle { dpi: 0, ..FigureStyle::default() }.validate().is_err());
+        for line_width in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(FigureStyle { line_width, ..FigureStyle::default() }.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Nuclear data access shared by the xsplot web app and command line.
+//!
+//! The [`catalog`] lists every reaction that can be plotted. [`reactions`] names
+//! the MT numbers. [`search`] tracks what the table shows and [`selection`]
+//! what is picked.
+//!
+//! [`source`] resolves entries to their JSON files on GitHub, a mirror or disk,
+//! as listed in the [`library`] registry. [`fetch`] reads them into plottable
+//! curves and [`cache`] keeps what was already read.
+//!
+//! [`export`] saves the curves as files. [`figure`] plots them, drawn by [`svg`]
+//! and [`matplotlib`] without a browser.
+//!
+//! Everything here builds for both wasm32 and native targets. The HDF5 export
+//! is the exception, only the command line offers it.
+pub mod cache;
+pub mod catalog;
+pub mod colours;
+pub mod endf;
+pub mod error;
+pub mod export;
+pub mod fetch;
+pub mod figure;
+pub mod library;
+pub mod matplotlib;
+pub mod nuclides;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod openmc;
+pub mod reactions;
+pub mod search;
+pub mod selection;
+pub mod source;
+pub mod svg;
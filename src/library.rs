@@ -0,0 +1,256 @@
+//! The evaluated libraries reactions can be plotted from and where their files live.
+//!
+//! The registry is plain data. The bundled one is `data/libraries.json` and
+//! users can layer their own file of the same form on top, in the browser's
+//! LocalStorage or on disk for the command line, to add a library such as a
+//! patched evaluation without rebuilding the app. Libraries missing from the
+//! bundled catalog have their index read at startup instead. Only ENDF/B-VIII.0
+//! and FENDL-3.2c are bundled, other evaluations such as JEFF, JENDL, TENDL or
+//! CENDL have to be registered this way with JSON files converted from them.
+use std::sync::{Arc, RwLock};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::catalog::Entry;
+use crate::fetch::convert_string;
+
+/// Where the reaction files are when a library does not give a `url_template`.
+const DEFAULT_URL_TEMPLATE: &str = "{base_url}/{key}.json";
+
+/// What a `url_template` may contain between braces.
+const PLACEHOLDERS: [&str; 9] = ["base_url", "key", "element", "nucleons", "isomer", "library", "projectile", "mt", "temperature"];
+
+/// One evaluated nuclear data library.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    /// Name used in the catalog and the file names, e.g. `FENDL-3.2c`
+    pub name: String,
+    /// Name shown in plot legends, `name` when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Evaluation the library is a release of, e.g. `FENDL`
+    #[serde(default)]
+    pub evaluation: String,
+    /// Release of the evaluation, e.g. `3.2c`
+    #[serde(default)]
+    pub version: String,
+    /// URL the reaction JSON files sit below
+    pub base_url: String,
+    /// Where one reaction's file is, with `{base_url}`, `{key}` (the file stem,
+    /// see [`convert_string`]) or any of `{element}`, `{nucleons}`, `{isomer}`,
+    /// `{library}`, `{projectile}`, `{mt}` and `{temperature}` filled in, any
+    /// other placeholder being an error when the registry is read. `{isomer}` is
+    /// empty for ground states and e.g. `m1` for the first metastable state.
+    /// Defaults to `{base_url}/{key}.json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_template: Option<String>,
+    /// JSON index of every reaction in the library, relative to `base_url`
+    pub index_file: String,
+    /// Temperatures in kelvin the library is processed at, any when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub temperatures: Vec<String>,
+}
+
+impl Library {
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Whether the library has the entry's temperature.
+    pub fn has_temperature(&self, temperature: &str) -> bool {
+        self.temperatures.is_empty() || self.temperatures.iter().any(|t| t == temperature)
+    }
+
+    /// Where the entry's file is downloaded from, `None` for metastable states
+    /// when the `url_template` has neither `{key}` nor `{isomer}` to tell them
+    /// from their ground state.
+    pub fn file_url(&self, entry: &Entry) -> Option<String> {
+        let template = self.url_template.as_deref().unwrap_or(DEFAULT_URL_TEMPLATE);
+        if entry.isomer != 0 && !template.contains("{key}") && !template.contains("{isomer}") {
+            return None;
+        }
+        let isomer = match entry.isomer {
+            0 => String::new(),
+            isomer => format!("m{}", isomer),
+        };
+        let url = template
+            .replace("{base_url}", self.base_url.trim_end_matches('/'))
+            .replace("{key}", &convert_string(entry))
+            .replace("{element}", &entry.element)
+            .replace("{nucleons}", &entry.nucleons.to_string())
+            .replace("{isomer}", &isomer)
+            .replace("{library}", &entry.library)
+            .replace("{projectile}", &entry.projectile)
+            .replace("{mt}", &entry.mt.to_string())
+            .replace("{temperature}", &entry.temperature);
+        Some(url)
+    }
+
+    /// The first placeholder of the `url_template` that [`Library::file_url`] would not fill in.
+    fn unknown_placeholder(&self) -> Option<&str> {
+        let mut rest = self.url_template.as_deref()?;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}')? + start;
+            let placeholder = &rest[start + 1..end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                return Some(placeholder);
+            }
+            rest = &rest[end + 1..];
+        }
+        None
+    }
+
+    pub fn index_url(&self) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), self.index_file)
+    }
+}
+
+/// Every library known to the plotter.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Registry {
+    pub libraries: Vec<Library>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::from_json(include_str!("../data/libraries.json")).expect("Failed to load the library registry")
+    }
+}
+
+impl Registry {
+    /// Reads a registry, rejecting URL templates with placeholders that would be left in the URLs.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let registry: Self = serde_json::from_str(json)?;
+        for library in &registry.libraries {
+            if let Some(placeholder) = library.unknown_placeholder() {
+                return Err(serde::de::Error::custom(format!(
+                    "the url_template of {} has an unknown placeholder {{{}}}, expected one of {}",
+                    library.name,
+                    placeholder,
+                    PLACEHOLDERS.join(", ")
+                )));
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Looks up a library by the name the catalog uses for it.
+    pub fn get(&self, name: &str) -> Option<&Library> {
+        self.libraries.iter().find(|library| library.name == name)
+    }
+
+    /// Adds the libraries of `overrides`, replacing the ones with the same name.
+    pub fn merge(&mut self, overrides: Registry) {
+        for library in overrides.libraries {
+            match self.libraries.iter_mut().find(|existing| existing.name == library.name) {
+                Some(existing) => *existing = library,
+                None => self.libraries.push(library),
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<Arc<Registry>> = RwLock::new(Arc::new(Registry::default()));
+}
+
+/// The registry the catalog and [`GitHub`](crate::source::GitHub) source consult.
+pub fn registry() -> Arc<Registry> {
+    match REGISTRY.read() {
+        Ok(registry) => registry.clone(),
+        Err(e) => {
+            error!("Error reading the library registry: {:?}", e);
+            e.into_inner().clone()
+        }
+    }
+}
+
+/// Replaces the registry, typically with the bundled one merged with the user's.
+pub fn set_registry(registry: Registry) {
+    match REGISTRY.write() {
+        Ok(mut current) => *current = Arc::new(registry),
+        Err(e) => error!("Error writing the library registry: {:?}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::EntryKey;
+
+    fn entry() -> Entry {
+        Entry::from_key(&"Fe_56_FENDL-3.2c_n_102_294K".parse::<EntryKey>().unwrap())
+    }
+
+    #[test]
+    fn user_libraries_replace_and_add_to_the_bundled_ones() {
+        let mut registry = Registry::default();
+        let bundled = registry.libraries.len();
+        registry.merge(
+            Registry::from_json(
+                r#"{"libraries": [
+                    {"name": "FENDL-3.2c", "base_url": "https://mirror.example.org/fendl/", "index_file": "index.json"},
+                    {"name": "JEFF-3.3", "base_url": "https://example.org/jeff", "index_file": "JEFF-3.3_index.json", "temperatures": ["294"]}
+                ]}"#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(registry.libraries.len(), bundled + 1);
+        let fendl = registry.get("FENDL-3.2c").unwrap();
+        assert_eq!(fendl.base_url, "https://mirror.example.org/fendl/");
+        assert!(fendl.temperatures.is_empty());
+        assert_eq!(fendl.file_url(&entry()).unwrap(), "https://mirror.example.org/fendl/Fe_56_FENDL-3.2c_n_102_294K.json");
+        assert_eq!(registry.get("JEFF-3.3").unwrap().index_url(), "https://example.org/jeff/JEFF-3.3_index.json");
+        assert!(registry.get("ENDFB-8.0").is_some());
+    }
+
+    #[test]
+    fn url_templates_fill_in_every_placeholder() {
+        let registry = Registry::from_json(
+            r#"{"libraries": [{
+                "name": "FENDL-3.2c",
+                "base_url": "https://example.org/data",
+                "url_template": "{base_url}/{library}/{projectile}/{element}{nucleons}/MT{mt}_{temperature}K/{key}.json",
+                "index_file": "index.json"
+            }]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            registry.libraries[0].file_url(&entry()).unwrap(),
+            "https://example.org/data/FENDL-3.2c/n/Fe56/MT102_294K/Fe_56_FENDL-3.2c_n_102_294K.json"
+        );
+
+        let e = Registry::from_json(
+            r#"{"libraries": [{"name": "X", "base_url": "https://example.org", "url_template": "{base_url}/{nuclide}.json", "index_file": "index.json"}]}"#,
+        )
+        .unwrap_err();
+        assert!(e.to_string().contains("unknown placeholder {nuclide}"));
+    }
+
+    #[test]
+    fn url_templates_tell_metastable_states_apart() {
+        let library = |url_template: &str| Library {
+            name: "ENDFB-8.0".to_string(),
+            display_name: None,
+            evaluation: String::new(),
+            version: String::new(),
+            base_url: "https://example.org".to_string(),
+            url_template: Some(url_template.to_string()),
+            index_file: "index.json".to_string(),
+            temperatures: Vec::new(),
+        };
+        let ground = Entry::from_key(&"Am_242_ENDFB-8.0_n_102_294K".parse::<EntryKey>().unwrap());
+        let isomer = Entry::from_key(&"Am_242m1_ENDFB-8.0_n_102_294K".parse::<EntryKey>().unwrap());
+
+        let by_nuclide = library("{base_url}/{element}{nucleons}{isomer}/{mt}.json");
+        assert_eq!(by_nuclide.file_url(&ground).unwrap(), "https://example.org/Am242/102.json");
+        assert_eq!(by_nuclide.file_url(&isomer).unwrap(), "https://example.org/Am242m1/102.json");
+        assert_eq!(library("{base_url}/{key}.json").file_url(&isomer).unwrap(), "https://example.org/Am_242m1_ENDFB-8.0_n_102_294K.json");
+
+        let without_state = library("{base_url}/{element}{nucleons}/{mt}.json");
+        assert_eq!(without_state.file_url(&ground).unwrap(), "https://example.org/Am242/102.json");
+        assert_eq!(without_state.file_url(&isomer), None);
+    }
+}
@@ -1,6 +1,11 @@
+#[cfg(target_arch = "wasm32")]
 mod types;
+#[cfg(target_arch = "wasm32")]
 mod sauce;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
 
+#[cfg(target_arch = "wasm32")]
 fn main() {
     console_error_panic_hook::set_once();
     tracing_wasm::set_as_global_default_with_config(
@@ -8,6 +13,16 @@ fn main() {
             .set_max_level(tracing::Level::DEBUG)
             .build(),
     );
+    types::libraries::apply_user_libraries();
 
     yew::Renderer::<sauce::App>::new().render();
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main]
+async fn main() {
+    if let Err(e) = cli::run().await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Writing a plotly figure as a Python script that draws it again with
+//! matplotlib, for restyling a figure or adding it to others in Python.
+//!
+//! The script holds every point of the curves, so it runs without xsplot or the
+//! reaction files. Sizes are converted from plotly's pixels to matplotlib's
+//! inches and points at 96 pixels to the inch.
+use std::fmt::Write as _;
+
+use plotly::Plot;
+
+use crate::figure::{AxisData, FigureData};
+
+/// Pixels per inch and points per pixel.
+const DPI: f64 = 96.0;
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// A Python string literal, JSON's escapes being valid Python ones.
+fn string(text: &str) -> String {
+    serde_json::to_string(text).unwrap()
+}
+
+fn float(value: f64) -> String {
+    if value.is_nan() {
+        "float(\"nan\")".to_string()
+    } else if value.is_infinite() {
+        format!("float(\"{}inf\")", if value < 0.0 { "-" } else { "" })
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn floats(values: &[f64]) -> String {
+    let values: Vec<String> = values.iter().map(|value| float(*value)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// The `set_xscale` or `set_yscale` argument, masking what a log axis cannot show as plotly does.
+fn scale(axis: &AxisData) -> &'static str {
+    if axis.is_log {
+        "\"log\", nonpositive=\"mask\""
+    } else {
+        "\"linear\""
+    }
+}
+
+/// The figure as a Python script that shows it with matplotlib, or saves it to
+/// the file named by its first argument.
+pub fn to_matplotlib(plot: &Plot) -> Result<String, String> {
+    let FigureData { width, height, font_family, font_size, x_axis, y_axis, traces } = FigureData::from_plot(plot)?;
+
+    let mut script = String::from(
+        "\"\"\"Cross sections plotted with xsplot, redrawn with matplotlib.
+
+Change the styling below and run the script to show the figure, or pass a file
+name to save it instead, e.g. python cross_sections_from_xsplot.py figure.png
+\"\"\"
+import sys
+
+import matplotlib.pyplot as plt
+
+# Label, colour, line width in points, energies in eV and cross sections of each curve
+curves = [
+",
+    );
+    for trace in &traces {
+        let _ = writeln!(
+            script,
+            "    (\n        {},\n        {},\n        {},\n        {},\n        {},\n    ),",
+            string(&trace.name),
+            string(&trace.colour),
+            float(trace.line_width * POINTS_PER_PIXEL),
+            floats(&trace.x),
+            floats(&trace.y)
+        );
+    }
+    script.push_str("]\n\n");
+
+    // CSS font lists, such as `"Open Sans", verdana, sans-serif`, become lists of names
+    let families: Vec<String> = font_family.split(',').map(|family| string(family.trim().trim_matches(['"', '\'']))).collect();
+    let _ = writeln!(script, "plt.rcParams[\"font.family\"] = [{}]", families.join(", "));
+    let _ = writeln!(script, "plt.rcParams[\"font.size\"] = {}", float(font_size * POINTS_PER_PIXEL));
+    let _ = write!(
+        script,
+        "
+fig, ax = plt.subplots(figsize=({}, {}), dpi={})
+for label, colour, line_width, energy, cross_section in curves:
+    ax.plot(energy, cross_section, label=label, color=colour, linewidth=line_width)
+ax.set_xscale({})
+ax.set_yscale({})
+ax.set_xlabel({})
+ax.set_ylabel({})
+ax.grid(True, color=\"#e5e5e5\")
+if curves:
+    ax.legend(loc=\"upper left\", bbox_to_anchor=(1.02, 1))
+fig.tight_layout()
+
+if len(sys.argv) > 1:
+    fig.savefig(sys.argv[1])
+else:
+    plt.show()
+",
+        float(width / DPI),
+        float(height / DPI),
+        DPI,
+        scale(&x_axis),
+        scale(&y_axis),
+        string(&x_axis.title),
+        string(&y_axis.title)
+    );
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::XsCache;
+    use crate::figure::{figure, FigureStyle};
+
+    #[test]
+    fn writes_the_curves_and_axes_as_python() {
+        let cache = XsCache {
+            keys: Vec::new(),
+            energy_values: vec![vec![1.0e-5, 2.0e7]],
+            cross_section_values: vec![vec![2.5, f64::NAN]],
+            checkbox_selected: vec![true],
+            labels: vec!["Fe56 (n,gamma) \"FENDL\"".to_string()],
+        };
+        let script = to_matplotlib(&figure(&cache, &["#1f77b4"], true, false, &FigureStyle::default())).unwrap();
+
+        assert!(script.contains(
+            "    (
+        \"Fe56 (n,gamma) \\\"FENDL\\\"\",
+        \"#1f77b4\",
+        1.5,
+        [1e-5, 20000000.0],
+        [2.5, float(\"nan\")],
+    ),"
+        ));
+        assert!(script.contains("plt.rcParams[\"font.family\"] = [\"Arial\", \"sans-serif\"]"));
+        assert!(script.contains("fig, ax = plt.subplots(figsize=(9.375, 6.25), dpi=96)"));
+        assert!(script.contains("ax.set_xscale(\"log\", nonpositive=\"mask\")\nax.set_yscale(\"linear\")"));
+        assert!(script.contains("ax.set_ylabel(\"Microscopic Cross Section [barns]\")"));
+    }
+}
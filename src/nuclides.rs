@@ -0,0 +1,145 @@
+//! Atomic numbers, masses and ENDF material numbers of the nuclides in the catalog.
+//!
+//! The reaction files only give a nuclide by element and mass number, which is
+//! enough to find everything the data file formats written by [`export`](crate::export)
+//! ask for except the mass, which is only known for the nuclides tabulated here.
+
+/// Element symbols by atomic number, with the mass number ENDF numbers the
+/// element's materials from, its lightest stable or long lived isotope.
+///
+/// Zero where ENDF/B has no materials of the element to number from.
+const ELEMENTS: [(&str, i32); 98] = [
+    ("H", 1), ("He", 3), ("Li", 6), ("Be", 9), ("B", 10), ("C", 12), ("N", 14), ("O", 16), ("F", 19), ("Ne", 20),
+    ("Na", 23), ("Mg", 24), ("Al", 27), ("Si", 28), ("P", 31), ("S", 32), ("Cl", 35), ("Ar", 36), ("K", 39), ("Ca", 40),
+    ("Sc", 45), ("Ti", 46), ("V", 50), ("Cr", 50), ("Mn", 55), ("Fe", 54), ("Co", 59), ("Ni", 58), ("Cu", 63), ("Zn", 64),
+    ("Ga", 69), ("Ge", 70), ("As", 75), ("Se", 74), ("Br", 79), ("Kr", 78), ("Rb", 85), ("Sr", 84), ("Y", 89), ("Zr", 90),
+    ("Nb", 93), ("Mo", 92), ("Tc", 99), ("Ru", 96), ("Rh", 103), ("Pd", 102), ("Ag", 107), ("Cd", 106), ("In", 113), ("Sn", 112),
+    ("Sb", 121), ("Te", 120), ("I", 127), ("Xe", 124), ("Cs", 133), ("Ba", 130), ("La", 138), ("Ce", 136), ("Pr", 141), ("Nd", 142),
+    ("Pm", 139), ("Sm", 144), ("Eu", 151), ("Gd", 152), ("Tb", 159), ("Dy", 156), ("Ho", 165), ("Er", 162), ("Tm", 169), ("Yb", 168),
+    ("Lu", 175), ("Hf", 174), ("Ta", 180), ("W", 180), ("Re", 185), ("Os", 184), ("Ir", 191), ("Pt", 190), ("Au", 197), ("Hg", 196),
+    ("Tl", 203), ("Pb", 204), ("Bi", 209), ("Po", 208), ("At", 0), ("Rn", 0), ("Fr", 0), ("Ra", 223), ("Ac", 225), ("Th", 227),
+    ("Pa", 229), ("U", 234), ("Np", 230), ("Pu", 235), ("Am", 235), ("Cm", 240), ("Bk", 240), ("Cf", 240),
+];
+
+/// Einsteinium and fermium run out of room in the four digit MAT numbers and
+/// are numbered one by one instead.
+const HEAVY_MATERIALS: [(&str, i32, u32); 6] = [
+    ("Es", 251, 9911), ("Es", 252, 9912), ("Es", 253, 9913), ("Es", 254, 9914), ("Es", 255, 9916), ("Fm", 255, 9936),
+];
+
+/// Measured binding energies in MeV of the light nuclides, from which their
+/// masses follow exactly.
+const LIGHT_BINDING_ENERGIES: [(u32, i32, f64); 16] = [
+    (1, 2, 2.224566), (1, 3, 8.481798), (2, 3, 7.718043), (2, 4, 28.295673), (3, 6, 31.994564), (3, 7, 39.244526),
+    (4, 9, 58.164976), (5, 10, 64.750770), (5, 11, 76.204810), (6, 12, 92.161726), (6, 13, 97.108060), (7, 14, 104.658596),
+    (7, 15, 115.491930), (8, 16, 127.619296), (8, 17, 131.762376), (8, 18, 139.807746),
+];
+
+/// Atomic weight ratios of heavier nuclides as ENDF/B-VIII.0 gives them, the
+/// 2020 Atomic Mass Evaluation masses divided by the neutron mass.
+const TABULATED_AWRS: [(&str, i32, f64); 10] = [
+    ("F", 19, 18.835196), ("Na", 23, 22.792277), ("Al", 27, 26.749750), ("Fe", 56, 55.454430), ("Co", 59, 58.426930),
+    ("Zr", 90, 89.132370), ("Au", 197, 195.274529), ("Pb", 208, 206.190030), ("U", 238, 236.005820), ("Pu", 239, 236.998590),
+];
+
+/// Rest masses in MeV.
+const NEUTRON_MASS: f64 = 939.565_42;
+const HYDROGEN_ATOM_MASS: f64 = 938.783_07;
+
+/// The atomic number of an element symbol such as `Fe`.
+pub fn atomic_number(element: &str) -> Option<u32> {
+    ELEMENTS.iter().position(|(symbol, _)| *symbol == element).map(|i| i as u32 + 1)
+}
+
+/// A nuclide in its ground or a metastable state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Nuclide {
+    pub element: String,
+    pub z: u32,
+    pub a: i32,
+    /// Metastable state, 0 for the ground state
+    pub isomer: u8,
+}
+
+impl Nuclide {
+    pub fn new(element: &str, nucleons: i32) -> Option<Self> {
+        let z = atomic_number(element).or(match element {
+            "Es" => Some(99),
+            "Fm" => Some(100),
+            _ => None,
+        })?;
+        (nucleons >= z as i32).then(|| Self { element: element.to_string(), z, a: nucleons, isomer: 0 })
+    }
+
+    /// The same nuclide in metastable state `isomer`.
+    pub fn in_state(self, isomer: u8) -> Self {
+        Self { isomer, ..self }
+    }
+
+    /// The nuclide as OpenMC names it, e.g. `Fe56` or `Am242_m1`.
+    pub fn name(&self) -> String {
+        match self.isomer {
+            0 => format!("{}{}", self.element, self.a),
+            isomer => format!("{}{}_m{}", self.element, self.a, isomer),
+        }
+    }
+
+    /// `1000 Z + A`, how ENDF and ACE identify the nuclide.
+    pub fn za(&self) -> u32 {
+        1000 * self.z + self.a as u32
+    }
+
+    /// The ENDF/B material number, `100 Z + 25` for the element's first
+    /// isotope and three more for every neutron after it, plus the metastable
+    /// state for isomers.
+    pub fn mat(&self) -> Option<u32> {
+        if self.isomer > 2 {
+            return None;
+        }
+        if let Some((_, _, mat)) = HEAVY_MATERIALS.iter().find(|(element, a, _)| *element == self.element && *a == self.a) {
+            return Some(*mat + self.isomer as u32);
+        }
+        let (_, first) = *ELEMENTS.get(self.z as usize - 1)?;
+        let mat = 100 * self.z as i32 + 25 + 3 * (self.a - first) + self.isomer as i32;
+        (first > 0 && mat > 100 * self.z as i32 && mat < 100 * (self.z as i32 + 1)).then_some(mat as u32)
+    }
+
+    /// Atomic mass in neutron masses, as ENDF gives it.
+    ///
+    /// Worked out from the measured binding energy for hydrogen and the light
+    /// nuclides, and taken from [`TABULATED_AWRS`] for the rest. `None` for
+    /// nuclides with no tabulated mass, rather than an estimate that would be
+    /// written into a file as if it were evaluated.
+    pub fn awr(&self) -> Option<f64> {
+        if let Some((_, _, awr)) = TABULATED_AWRS.iter().find(|(element, a, _)| *element == self.element && *a == self.a) {
+            return Some(*awr);
+        }
+        let binding_energy = match LIGHT_BINDING_ENERGIES.iter().find(|(z, a, _)| *z == self.z && *a == self.a) {
+            Some((_, _, binding_energy)) => *binding_energy,
+            None if self.a == 1 => 0.0,
+            None => return None,
+        };
+        let (z, n) = (self.z as f64, (self.a - self.z as i32) as f64);
+        Some((z * HYDROGEN_ATOM_MASS + n * NEUTRON_MASS - binding_energy) / NEUTRON_MASS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// AME2020 atomic masses divided by the neutron mass, as ENDF/B-VIII.0 gives them.
+    const EVALUATED_AWRS: [(&str, i32, f64); 3] = [("H", 1, 0.999167), ("Li", 6, 5.963449), ("O", 16, 15.857510)];
+
+    #[test]
+    fn masses_are_measured_or_tabulated() {
+        for (element, a, evaluated) in EVALUATED_AWRS {
+            let awr = Nuclide::new(element, a).unwrap().awr().unwrap();
+            let error = ((awr - evaluated) / evaluated).abs();
+            assert!(error < 1e-5, "{}{}: {} against {}, {:.1e} off", element, a, awr, evaluated, error);
+        }
+        assert_eq!(Nuclide::new("Fe", 56).unwrap().awr(), Some(55.454430));
+        assert_eq!(Nuclide::new("Fe", 54).unwrap().awr(), None);
+        assert_eq!(Nuclide::new("C", 14).unwrap().awr(), None);
+    }
+}
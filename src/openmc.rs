@@ -0,0 +1,210 @@
+//! Writing cross sections as an HDF5 file laid out like OpenMC's incident
+//! neutron data, so plotted reactions can be read by `openmc.data` or used in
+//! place of a library's own file.
+//!
+//! Every nuclide is a group such as `/Fe56` holding its energy grid at each
+//! temperature in `energy/294K`, `kT` in `kTs/294K` and each reaction's cross
+//! section in `reactions/reaction_102/294K/xs`. OpenMC puts all reactions of a
+//! nuclide on one grid, so the grid is every energy of the nuclide's selected
+//! reactions and each reaction is interpolated onto it from its threshold.
+//!
+//! Only cross sections are written: there are no reaction products, angular or
+//! energy distributions or Q values, which the reaction files do not carry.
+//! Native builds only, the browser keeps to the text formats.
+use std::collections::BTreeMap;
+
+use hdf5_pure::{AttrValue, FileBuilder};
+
+use crate::export::interpolate;
+use crate::fetch::XsCache;
+use crate::nuclides::Nuclide;
+use crate::reactions::{get_reaction_name_map, reaction_label};
+
+/// Version of OpenMC's data format the file follows.
+const VERSION: [i64; 2] = [3, 0];
+
+/// Boltzmann constant in eV/K, for the `kTs` OpenMC keeps temperatures as.
+const BOLTZMANN: f64 = 8.617_333_262e-5;
+
+/// Reactions that are sums of others, which OpenMC skips when sampling.
+const REDUNDANT: [i32; 12] = [1, 3, 4, 27, 101, 203, 204, 205, 206, 207, 301, 444];
+
+/// One reaction's curve at one temperature.
+type Curve = (Vec<f64>, Vec<f64>);
+
+/// The selected reactions of one nuclide, by MT and then temperature.
+struct NuclideData<'a> {
+    nuclide: Nuclide,
+    awr: f64,
+    library: &'a str,
+    reactions: BTreeMap<i32, BTreeMap<&'a str, Curve>>,
+}
+
+impl NuclideData<'_> {
+    /// OpenMC's name for the nuclide, e.g. `Fe56` or `Am242_m1`.
+    fn name(&self) -> String {
+        self.nuclide.name()
+    }
+
+    /// Every energy any reaction has at `temperature`, ascending.
+    fn energy_grid(&self, temperature: &str) -> Vec<f64> {
+        let mut energies: Vec<f64> = self
+            .reactions
+            .values()
+            .filter_map(|curves| curves.get(temperature))
+            .flat_map(|(energy_values, _)| energy_values.iter().copied())
+            .collect();
+        energies.sort_by(f64::total_cmp);
+        energies.dedup();
+        energies
+    }
+
+    fn temperatures(&self) -> Vec<&str> {
+        let mut temperatures: Vec<&str> = self.reactions.values().flat_map(|curves| curves.keys().copied()).collect();
+        temperatures.sort_by_key(|temperature| temperature.parse::<u32>().unwrap_or(u32::MAX));
+        temperatures.dedup();
+        temperatures
+    }
+}
+
+/// The curves on `grid` from the first point at or above their threshold,
+/// with that point's index, and zero above their last energy.
+fn on_grid(grid: &[f64], (energy_values, cross_section_values): &Curve) -> (usize, Vec<f64>) {
+    let threshold = grid.partition_point(|energy| *energy < energy_values[0]);
+    let values = grid[threshold..]
+        .iter()
+        .map(|energy| interpolate(energy_values, cross_section_values, *energy).unwrap_or(0.0))
+        .collect();
+    (threshold, values)
+}
+
+/// The curves as an OpenMC style HDF5 file, failing for reactions of other
+/// projectiles than neutrons, which the layout is for, for nuclides with no
+/// tabulated mass and for nuclides selected from more than one library, which
+/// OpenMC keeps in separate files.
+pub fn to_hdf5(cache: &XsCache) -> Result<Vec<u8>, String> {
+    let mut nuclides: Vec<NuclideData> = Vec::new();
+    for (i, key) in cache.keys.iter().enumerate() {
+        if key.projectile != "n" {
+            return Err(format!("{} is not an incident neutron reaction, only those can be written as HDF5", key));
+        }
+        let nuclide = Nuclide::new(&key.element, key.nucleons).ok_or_else(|| format!("{} is not a known nuclide", key.nuclide()))?.in_state(key.isomer);
+        if cache.energy_values[i].is_empty() {
+            continue;
+        }
+        let data = match nuclides.iter_mut().find(|data| data.nuclide == nuclide) {
+            Some(data) => data,
+            None => {
+                let awr = nuclide.awr().ok_or_else(|| format!("{} has no tabulated atomic weight ratio", key.nuclide()))?;
+                nuclides.push(NuclideData { nuclide, awr, library: &key.library, reactions: BTreeMap::new() });
+                nuclides.last_mut().unwrap()
+            }
+        };
+        if data.library != key.library {
+            return Err(format!("{} is selected for more than one library, export them one at a time", data.name()));
+        }
+        data.reactions
+            .entry(key.mt)
+            .or_default()
+            .insert(&key.temperature, (cache.energy_values[i].clone(), cache.cross_section_values[i].clone()));
+    }
+
+    let reaction_name = get_reaction_name_map();
+    let mut builder = FileBuilder::new();
+    builder.set_attr("filetype", AttrValue::String("data_neutron".to_string()));
+    builder.set_attr("version", AttrValue::I64Array(VERSION.to_vec()));
+    for data in &nuclides {
+        let mut group = builder.create_group(&data.name());
+        group.set_attr("Z", AttrValue::I64(data.nuclide.z as i64));
+        group.set_attr("A", AttrValue::I64(data.nuclide.a as i64));
+        group.set_attr("metastable", AttrValue::I64(data.nuclide.isomer as i64));
+        group.set_attr("atomic_weight_ratio", AttrValue::F64(data.awr));
+        group.set_attr("library", AttrValue::String(data.library.to_string()));
+
+        let temperatures = data.temperatures();
+        let grids: BTreeMap<&str, Vec<f64>> = temperatures.iter().map(|temperature| (*temperature, data.energy_grid(temperature))).collect();
+
+        let mut energy = group.create_group("energy");
+        let mut kts = group.create_group("kTs");
+        for temperature in &temperatures {
+            let name = format!("{}K", temperature);
+            energy.create_dataset(&name).with_f64_data(&grids[temperature]);
+            let kelvin: f64 = temperature.parse().map_err(|_| format!("{} K is not a temperature", temperature))?;
+            kts.create_dataset(&name).with_f64_data(&[kelvin * BOLTZMANN]).with_shape(&[]);
+        }
+        group.add_group(energy.finish());
+        group.add_group(kts.finish());
+
+        let mut reactions = group.create_group("reactions");
+        for (mt, curves) in &data.reactions {
+            let mut reaction = reactions.create_group(&format!("reaction_{:03}", mt));
+            reaction.set_attr("mt", AttrValue::I64(*mt as i64));
+            reaction.set_attr("label", AttrValue::String(reaction_label("n", *mt, &reaction_name)));
+            reaction.set_attr("Q_value", AttrValue::F64(0.0));
+            reaction.set_attr("center_of_mass", AttrValue::I64(1));
+            reaction.set_attr("redundant", AttrValue::I64(REDUNDANT.contains(mt) as i64));
+            for (temperature, curve) in curves {
+                let (threshold, values) = on_grid(&grids[temperature], curve);
+                let mut at_temperature = reaction.create_group(&format!("{}K", temperature));
+                at_temperature
+                    .create_dataset("xs")
+                    .with_f64_data(&values)
+                    .set_attr("threshold_idx", AttrValue::I64(threshold as i64));
+                reaction.add_group(at_temperature.finish());
+            }
+            reactions.add_group(reaction.finish());
+        }
+        group.add_group(reactions.finish());
+        builder.add_group(group.finish());
+    }
+    builder.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::EntryKey;
+    use hdf5_pure::File;
+
+    #[test]
+    fn reactions_share_the_nuclide_energy_grid() {
+        let keys: Vec<EntryKey> = ["Fe_56_FENDL-3.2c_n_102_294K", "Fe_56_FENDL-3.2c_n_16_294K"].map(|key| key.parse().unwrap()).into();
+        let cache = XsCache {
+            keys,
+            energy_values: vec![vec![1.0, 2.0e6, 2.0e7], vec![1.1e7, 2.0e7]],
+            cross_section_values: vec![vec![2.0, 0.5, 0.1], vec![0.0, 0.4]],
+            checkbox_selected: vec![true, true],
+            labels: vec![String::new(), String::new()],
+        };
+        let file = File::from_bytes(to_hdf5(&cache).unwrap()).unwrap();
+
+        assert_eq!(file.root().attrs().unwrap()["filetype"], AttrValue::String("data_neutron".to_string()));
+        let nuclide = file.group("Fe56").unwrap().attrs().unwrap();
+        assert_eq!((&nuclide["Z"], &nuclide["A"]), (&AttrValue::I64(26), &AttrValue::I64(56)));
+        assert_eq!(file.dataset("Fe56/energy/294K").unwrap().read_f64().unwrap(), [1.0, 2.0e6, 1.1e7, 2.0e7]);
+        let kt = file.dataset("Fe56/kTs/294K").unwrap().read_f64().unwrap();
+        assert!((kt[0] - 0.025335).abs() < 1e-6);
+
+        let capture = file.dataset("Fe56/reactions/reaction_102/294K/xs").unwrap();
+        assert_eq!(capture.read_f64().unwrap(), [2.0, 0.5, 0.3, 0.1]);
+        assert_eq!(capture.attrs().unwrap()["threshold_idx"], AttrValue::I64(0));
+        let n2n = file.dataset("Fe56/reactions/reaction_016/294K/xs").unwrap();
+        assert_eq!(n2n.read_f64().unwrap(), [0.0, 0.4]);
+        assert_eq!(n2n.attrs().unwrap()["threshold_idx"], AttrValue::I64(2));
+        assert_eq!(file.group("Fe56/reactions/reaction_016").unwrap().attrs().unwrap()["label"], AttrValue::String("(n,2n)".to_string()));
+    }
+
+    #[test]
+    fn refuses_other_projectiles_than_neutrons() {
+        for key in ["Fe_56_FENDL-3.2c_p_5_294K", "Fe_56_FENDL-3.2c_g_5_294K"] {
+            let cache = XsCache {
+                keys: vec![key.parse().unwrap()],
+                energy_values: vec![vec![1.0e6, 2.0e7]],
+                cross_section_values: vec![vec![0.1, 0.2]],
+                checkbox_selected: vec![true],
+                labels: vec![String::new()],
+            };
+            assert_eq!(to_hdf5(&cache), Err(format!("{} is not an incident neutron reaction, only those can be written as HDF5", key)));
+        }
+    }
+}
@@ -1,73 +1,8 @@
+//! ENDF MT reaction numbers and their short product names.
 use std::collections::HashMap;
-use std::error::Error;
-use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Data {
-    pub data: Vec<Entry>,
-}
-
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Entry {
-    pub id: i32,
-    pub element: String,
-    pub nucleons: i32,
-    pub library: String,
-    pub reaction: String,
-    pub mt: i32,
-    pub temperature: String,
-}
-
-impl Default for Data {
-    fn default() -> Self {
-        let data = load_data_from_csv(include_str!("table_data.csv")).expect("Failed to load data from CSV file");
-        Self { data }
-    }
-}
-
-
-fn load_data_from_csv(csv_data: &str) -> Result<Vec<Entry>, Box<dyn Error>> {
-    let expected_headers = vec!["id", "element", "nucleons", "library", "incident_particle", "mt", "temperature"];
-
-    let reaction_name = get_reaction_name_map();
-    
-    let mut lines = csv_data.lines();
-
-    // Check the headers
-    if let Some(header_line) = lines.next() {
-        let headers: Vec<&str> = header_line.split(',').collect();
-        if headers != expected_headers {
-            return Err(format!("CSV header does not match expected columns. Found: {:?}, Expected: {:?}", headers, expected_headers).into());
-        }
-    } else {
-        return Err("Failed to read the header line from the CSV data".into());
-    }
-
-    let mut data = Vec::new();
-    for line in lines {
-        let fields: Vec<&str> = line.split(',').collect();
-        if fields.len() != expected_headers.len() {
-            return Err(format!("CSV row does not match expected number of columns. Found: {}, Expected: {}", fields.len(), expected_headers.len()).into());
-        }
-        let mt: i32 = fields[5].parse()?;
-        let reaction_description = reaction_name.get(&mt).cloned().unwrap_or_else(|| "unknown".to_string());
-        let reaction = format!("(n,{})", reaction_description);
-        let entry = Entry {
-            id: fields[0].parse()?,
-            element: fields[1].to_string(),
-            nucleons: fields[2].parse()?,
-            library: fields[3].to_string(),
-            reaction,
-            mt,
-            temperature: fields[6].to_string(),
-        };
-        data.push(entry);
-    }
-    Ok(data)
-}
-
-
-fn get_reaction_name_map() -> HashMap<i32, String> {
+/// Maps MT numbers to the product part of the reaction label, e.g. 102 -> "gamma".
+pub fn get_reaction_name_map() -> HashMap<i32, String> {
     let mut reaction_name = HashMap::new();
     reaction_name.insert(1, "total".to_string());
     reaction_name.insert(2, "elastic".to_string());
@@ -207,22 +142,43 @@ fn get_reaction_name_map() -> HashMap<i32, String> {
     reaction_name
 }
 
-
-pub enum DataActions {
-    #[allow(dead_code)]
-    RemoveData(i32),
+/// The projectile part of the reaction label for an `incident_particle`, e.g. "h" -> "3He".
+///
+/// The catalog stores the first letter of the particle name, as the JSON file
+/// names do, but the full names are understood too. Unknown particles are kept as they are.
+pub fn projectile_symbol(incident_particle: &str) -> &str {
+    match incident_particle {
+        "n" | "neutron" => "n",
+        "p" | "proton" => "p",
+        "d" | "deuteron" => "d",
+        "t" | "triton" => "t",
+        "h" | "helion" | "He3" | "3He" => "3He",
+        "a" | "alpha" => "alpha",
+        "g" | "gamma" | "photon" => "gamma",
+        other => other,
+    }
 }
 
-impl yew::Reducible for Data {
-    type Action = DataActions;
-
-    fn reduce(self: std::rc::Rc<Self>, action: Self::Action) -> std::rc::Rc<Self> {
-        let mut new = (*self).clone();
-        match action {
-            DataActions::RemoveData(id) => {
-                new.data.retain(|entry| entry.id != id);
-            }
-        }
-        std::rc::Rc::new(new)
+/// The letter the JSON file names use for a particle as a library index names it,
+/// e.g. "photon" -> "g".
+///
+/// Unknown names give `None` rather than their first letter, which would file
+/// photons under the protons' `p`.
+pub fn particle_letter(name: &str) -> Option<&'static str> {
+    match name.trim().to_lowercase().as_str() {
+        "n" | "neutron" => Some("n"),
+        "p" | "proton" => Some("p"),
+        "g" | "gamma" | "photon" => Some("g"),
+        "d" | "deuteron" => Some("d"),
+        "t" | "triton" => Some("t"),
+        "h" | "helion" | "he3" | "3he" => Some("h"),
+        "a" | "alpha" => Some("a"),
+        _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// The label of a reaction, e.g. "(n,gamma)" or "(p,2n)".
+pub fn reaction_label(incident_particle: &str, mt: i32, reaction_name: &HashMap<i32, String>) -> String {
+    let products = reaction_name.get(&mt).map(String::as_str).unwrap_or("unknown");
+    format!("({},{})", projectile_symbol(incident_particle), products)
+}
@@ -1,266 +1,240 @@
-use std::collections::HashSet;
-use yew::{Callback, classes, function_component, Html, html, TargetCast, use_reducer, use_state};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use yew::{Callback, classes, function_component, Html, html, TargetCast, use_mut_ref, use_reducer, use_state};
 use serde::Serialize;
-use web_sys::{console, HtmlInputElement, InputEvent};
-use yew_hooks::use_set;
-use yew_hooks::use_async;
+use web_sys::{HtmlInputElement, HtmlSelectElement, InputEvent};
+use yew_hooks::use_map;
 // use yew_custom_components::pagination::Pagination;
 use yew_custom_components::table::{Options, Table};
 use yew_custom_components::table::types::{ColumnBuilder, TableData};
-use crate::types::mock_data::Entry;
+use xsplot::catalog::EntryKey;
+use xsplot::error::FetchError;
+use xsplot::export::ExportFormat;
+use xsplot::catalog::{catalog, manifest};
+use xsplot::fetch::{fetch_legacy_ids, fetch_library_catalog, fetch_missing_libraries, generate_cache};
+use xsplot::library::registry;
+use xsplot::search::Search;
+use xsplot::selection::Selection;
+use xsplot::source::PickedFiles;
+use super::plot::{data_source, save_file, PlotComponent, TraceOrder};
+use super::query::PlotQuery;
+use super::state::{CatalogState, DataActions, EnabledLibraries, EnabledLibrariesActions, SelectionActions, SelectionState};
+use super::Route;
+use yew_router::prelude::{use_location, use_navigator};
 
-use plotly::{Plot, Scatter};
-use plotly::layout::{AxisType};
 use yew::prelude::*;
-use serde::Deserialize;
-
-use wasm_bindgen_futures::spawn_local;
-use web_sys::HtmlElement;
-use web_sys::{Blob, BlobPropertyBag, Url};
-use wasm_bindgen::JsValue;
-use js_sys::Array;
-use web_sys::wasm_bindgen::JsCast;
-use serde_json::Value;
-
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ReactionData {
-    #[serde(rename = "energy")]
-    energy_values: Vec<f64>,
-    #[serde(rename = "cross section")]
-    cross_section_values: Vec<f64>,
-}
-
-#[derive(PartialEq, Clone, Serialize)]
-pub struct XsCache {
-    pub energy_values: Vec<Vec<f64>>,
-    pub cross_section_values: Vec<Vec<f64>>,
-    pub checkbox_selected: Vec<bool>,
-    pub labels: Vec<String>,
-}
-
-#[derive(Properties, PartialEq)]
-pub struct PlotProps {
-    pub selected_ids: HashSet<i32>,
-    pub is_y_log: UseStateHandle<bool>,
-    pub is_x_log: UseStateHandle<bool>,
-    pub clear_plot_callback: Callback<MouseEvent>,
-}
-
-#[function_component(PlotComponent)]
-pub fn plot_component(props: &PlotProps) -> Html {
-    let selected_ids = &props.selected_ids;
-    let is_y_log = props.is_y_log.clone();
-    let is_x_log = props.is_x_log.clone();
-
-    let p = use_async::<_, _, ()>({
-        let selected_ids = selected_ids.clone();
-        let is_y_log = is_y_log.clone();
-        let is_x_log = is_x_log.clone();
-
-        async move {
-            let cache = generate_cache(&selected_ids).await;
-
-            let id = "plot-div";
-            let mut plot = Plot::new();
-
-            let mut heat_or_damage_plotted: bool = false;
-            let mut cross_section_plotted: bool = false;
-
-            for (i, (energy, cross_section)) in cache.energy_values.iter().zip(&cache.cross_section_values).enumerate() {
-                if cache.checkbox_selected[i] {
-                    let trace = Scatter::new(energy.clone(), cross_section.clone())
-                        .name(&format!("{}", cache.labels[i]));
-                    plot.add_trace(trace);
-                    if cache.labels[i].contains("heat") || cache.labels[i].contains("damage") {
-                        heat_or_damage_plotted = true;
-                    }
-                    if !cache.labels[i].contains("heat") && !cache.labels[i].contains("damage") {
-                        cross_section_plotted = true;
-                    }
-                }
-            }
-
-            let x_axis_title = if cross_section_plotted && heat_or_damage_plotted {
-                "Microscopic Cross Section [barns], Heating Cross Section [eV-barn]"
-            } else if cross_section_plotted {
-                "Microscopic Cross Section [barns]"
-            } else if heat_or_damage_plotted {
-                "Heating Cross Section [eV-barn]"
-            } else {
-                "" // no data plotted
-            };
-
-            let y_axis = plotly::layout::Axis::new()
-                .title(x_axis_title)
-                // .show_line(true)
-                .zero_line(true)
-                // .range(0)  not sure how to set lower value
-                .type_(if *is_y_log { AxisType::Log } else { AxisType::Linear });
-            
-            let x_axis = plotly::layout::Axis::new()
-                .title("Energy [eV]")
-                .zero_line(true)
-                // .show_line(true)
-                .type_(if *is_x_log { AxisType::Log } else { AxisType::Linear });
-
-            let layout = plotly::Layout::new()
-                // .title("Cross sections plotted with XSPlot.com")
-                .show_legend(true)
-                .x_axis(x_axis)
-                .y_axis(y_axis);
-            
-            plot.set_layout(layout);
 
-            plotly::bindings::new_plot(id, &plot).await;
-            Ok(())
-        }
-    });
-
-    use_effect_with((selected_ids.clone(), is_y_log.clone(), is_x_log.clone()), move |_| {
-        p.run();
-    });
-
-    html! {
-        <div id="plot-div"></div>
-    }
-}
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use crate::types::libraries::{clear_user_libraries, enabled_libraries, save_enabled_libraries, save_user_libraries, user_libraries};
+use web_sys::Url;
 
-async fn generate_cache(selected: &HashSet<i32>) -> XsCache {
-    // TODO add name to this so that when adding a trace the name can be set
-    let mut cache_energy_values = Vec::new();
-    let mut cache_cross_section_values = Vec::new();
-    let mut cache_checkbox_selected = Vec::new();
-    let mut cache_labels = Vec::new();
-    console::log_1(&serde_wasm_bindgen::to_value("selected_id").unwrap());
-    for &selected_id in selected.iter() {
-        let (energy, cross_section, label) = get_values_by_id(selected_id as i32).await.expect("Failed to get values by ID");
-        cache_energy_values.push(energy);
-        cache_cross_section_values.push(cross_section);
-        cache_checkbox_selected.push(true);
-        cache_labels.push(label);
-        console::log_1(&selected_id.clone().into());
-    }
 
-    XsCache {
-        energy_values: cache_energy_values,
-        cross_section_values: cache_cross_section_values,
-        checkbox_selected: cache_checkbox_selected,
-        labels: cache_labels,
+async fn download_xs_cache(selection: Selection, data: UseReducerHandle<CatalogState>, format: ExportFormat, local_files: Option<Rc<PickedFiles>>, on_fetch_error: Callback<(EntryKey, Option<FetchError>)>) {
+    let (cache, failures) = generate_cache(selection.keys(), &data, data_source(&local_files)).await;
+    for (key, e) in failures {
+        on_fetch_error.emit((key, Some(e)));
     }
-}
 
-async fn get_values_by_id(id: i32) -> Result<(Vec<f64>, Vec<f64>, String), reqwest::Error> {
-    let data = crate::types::mock_data::Data::default();
-    let entry = data.data.iter().find(|entry| entry.id == id).expect("Entry not found");
-    let output = convert_string(entry);
-    console::log_1(&serde_wasm_bindgen::to_value(&"output").unwrap());
-    console::log_1(&serde_wasm_bindgen::to_value(&output).unwrap());
-    console::log_1(&serde_wasm_bindgen::to_value(&"entry.library").unwrap());
-    console::log_1(&serde_wasm_bindgen::to_value(&entry.library).unwrap());
-
-    let url = match entry.library.as_str() {
-        "ENDFB-8.0" => format!("https://raw.githubusercontent.com/openmc-data-storage/ENDF-B-VIII.0-NNDC-json/refs/heads/main/json_files/{output}.json"),
-        "FENDL-3.2c" => format!("https://raw.githubusercontent.com/openmc-data-storage/FENDL-3.2c-json/refs/heads/main/FENDL-3.2c_json/{output}.json"),
-        _ => panic!("Unsupported library: {}", entry.library),
+    let contents = match format.export(&cache) {
+        Ok(contents) => contents,
+        Err(e) => {
+            tracing::error!("Error exporting {}: {}", format, e);
+            return;
+        }
     };
 
-    console::log_1(&serde_wasm_bindgen::to_value(&url).unwrap());
-    let downloaded_reaction_data: ReactionData = reqwest::get(url)
-        .await?
-        .json()
-        .await?;
-        console::log_1(&serde_wasm_bindgen::to_value("downloaded data").unwrap());
-        console::log_1(&serde_wasm_bindgen::to_value(&downloaded_reaction_data).unwrap());
-    
-    let label = entry.element.clone() + entry.nucleons.to_string().as_str() + " " + entry.reaction.as_str()+ " " +entry.library.as_str(); //   +" " + entry.temperature.as_str();
-    Ok((downloaded_reaction_data.energy_values, downloaded_reaction_data.cross_section_values, label))
-}
-
-
-fn convert_string(entry: &Entry) -> String {
-    let element = entry.element.clone();
-    let nucleons = entry.nucleons.clone();
-    let library = entry.library.clone();
-    // let reaction = entry.reaction.clone();  // not needed as we have MT number
-    let particle:char = 'n';  // entry.particle.clone();
-    let mt = entry.mt.clone();
-    let temperature = entry.temperature.clone();
-    let output = format!("{}_{}_{}_{}_{}_{}K", element, nucleons, library, particle, mt, temperature);
-    output
+    save_file(&contents, format.mime_type(), &format.file_name());
 }
 
-async fn download_xs_cache(selected_ids: HashSet<i32>) {
-    let cache = generate_cache(&selected_ids).await;
-
-    // Convert the cache data to a pretty-printed JSON string
-    let json_data = serde_json::to_string_pretty(&cache).unwrap();
-
-    // Deserialize the JSON string into a serde_json::Value
-    let mut json_value: Value = serde_json::from_str(&json_data).unwrap();
-
-    // Remove the "checkbox_selected" key
-    if let Value::Object(ref mut map) = json_value {
-        map.remove("checkbox_selected");
-    }
-
-    // Serialize the modified JSON value back to a string
-    let modified_json_data = serde_json::to_string_pretty(&json_value).unwrap();
-
-
-    // Create a Blob from the JSON data
-    let blob_options = BlobPropertyBag::new();
-    blob_options.set_type("application/json");
-
-    let blob = Blob::new_with_str_sequence_and_options(
-        &Array::of1(&JsValue::from_str(&modified_json_data)),
-        &blob_options,
-    ).unwrap();
-
-    // Create a URL for the Blob
-    let url = Url::create_object_url_with_blob(&blob).unwrap();
-
-    // Create a hidden anchor element to trigger the download
+/// Where a file copied next to the page is served, such as the per-library
+/// catalog files in `catalog/`.
+fn page_url(path: &str) -> String {
     let document = web_sys::window().unwrap().document().unwrap();
-    let a = document.create_element("a").unwrap();
-    a.set_attribute("href", &url).unwrap();
-    a.set_attribute("download", "cross_sections_from_xsplot.json").unwrap();
-    a.set_attribute("style", "display: none;").unwrap();
-    document.body().unwrap().append_child(&a).unwrap();
-
-    // Trigger the download
-    let a: HtmlElement = a.dyn_into::<HtmlElement>().unwrap();
-    a.click();
-
-    // Clean up by revoking the object URL
-    Url::revoke_object_url(&url).unwrap();
-    document.body().unwrap().remove_child(&a).unwrap();
+    let base = document.base_uri().ok().flatten().unwrap_or_default();
+    match Url::new_with_base(path, &base) {
+        Ok(url) => url.href(),
+        Err(_) => path.to_string(),
+    }
 }
 
 #[function_component(Home)]
 pub fn home() -> Html {
-    let data = use_reducer(crate::types::mock_data::Data::default);
-    let mock_data = (*data).clone();
-
-    let element_search_term = use_state(|| None::<String>);
-    let nucleons_search_term = use_state(|| None::<String>);
-    let reaction_search_term = use_state(|| None::<String>);
-    let mt_search_term = use_state(|| None::<String>);
-    let library_search_term = use_state(|| None::<String>);
+    let data = use_reducer(|| CatalogState(catalog().clone()));
+    let mock_data = &*data;
+
+    // A shared link restores the plot it was copied from
+    let location = use_location();
+    let navigator = use_navigator();
+    let initial_query = use_state(|| {
+        location
+            .and_then(|location| location.query::<PlotQuery>().ok())
+            .unwrap_or_default()
+    });
+
+    let element_search_term = use_state(|| initial_query.element.clone());
+    let nucleons_search_term = use_state(|| initial_query.nucleons.clone());
+    let reaction_search_term = use_state(|| initial_query.reaction.clone());
+    let mt_search_term = use_state(|| initial_query.mt.clone());
+    let library_search_term = use_state(|| initial_query.library.clone());
+    let projectile_search_term = use_state(|| initial_query.projectile.clone());
+    let temperature_search_term = use_state(|| initial_query.temperature.clone());
     let element_search = (*element_search_term).as_ref().cloned();
     let nucleons_search = (*nucleons_search_term).as_ref().cloned();
     let reaction_search = (*reaction_search_term).as_ref().cloned();
     let mt_search = (*mt_search_term).as_ref().cloned();
     let library_search = (*library_search_term).as_ref().cloned();
+    let projectile_search = (*projectile_search_term).as_ref().cloned();
+    let temperature_search = (*temperature_search_term).as_ref().cloned();
 
     let page = use_state(|| 0usize);
-    let current_page = (*page).clone();
+    let current_page = *page;
+
+    let search = Search {
+        element: element_search.clone(),
+        nucleons: nucleons_search.clone(),
+        reaction: reaction_search.clone(),
+        mt: mt_search.clone(),
+        library: library_search.clone(),
+        projectile: projectile_search.clone(),
+        temperature: temperature_search.clone(),
+    };
 
-    let selected_ids = use_set(HashSet::<i32>::new());
-    let sum = selected_ids.current().len();
+    let selection = {
+        let initial_query = initial_query.clone();
+        let data = data.clone();
+        use_reducer(move || SelectionState(initial_query.keys(&data).into_iter().collect::<Selection>()))
+    };
+    let sum = selection.len();
+
+    // Only the catalog libraries listed in the table are downloaded, always
+    // including the ones a shared link selected reactions from
+    let enabled = use_reducer(|| {
+        let mut enabled = EnabledLibraries(
+            initial_query
+                .libraries()
+                .or_else(enabled_libraries)
+                .unwrap_or_else(|| manifest().libraries.iter().map(|library| library.name.clone()).collect()),
+        );
+        enabled.include(selection.iter().map(|key| &key.library));
+        enabled
+    });
 
-    let is_y_log = use_state(|| true);
-    let is_x_log = use_state(|| true);
+    // Links from before entries had keys select reactions by the row numbers of the catalog then
+    let legacy_notice = use_state(|| None::<String>);
+    {
+        let selection = selection.clone();
+        let enabled = enabled.clone();
+        let legacy_notice = legacy_notice.clone();
+        let legacy_ids = initial_query.legacy_ids();
+        use_effect_with((), move |_| {
+            if !legacy_ids.is_empty() {
+                spawn_local(async move {
+                    match fetch_legacy_ids(&page_url("legacy_ids.bin")).await {
+                        Ok(table) => {
+                            let keys: Vec<EntryKey> = legacy_ids.iter().filter_map(|id| table.get(*id).cloned()).collect();
+                            let missing = legacy_ids.len() - keys.len();
+                            enabled.dispatch(EnabledLibrariesActions::Include(keys.iter().map(|key| key.library.clone()).collect()));
+                            selection.dispatch(SelectionActions::Extend(keys));
+                            if missing > 0 {
+                                legacy_notice.set(Some(format!("{} of the reactions selected by this older link do not exist and were left out.", missing)));
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("Error reading the legacy ids: {}", e);
+                            legacy_notice.set(Some(format!("This older link selects reactions by number, which could not be looked up: {}", e)));
+                        }
+                    }
+                });
+            }
+        });
+    }
+    let requested_libraries = use_mut_ref(HashSet::<String>::new);
+    let library_errors = use_map(HashMap::<String, FetchError>::new());
+
+    {
+        let data = data.clone();
+        let requested_libraries = requested_libraries.clone();
+        let library_errors = library_errors.clone();
+        use_effect_with((*enabled).clone(), move |enabled| {
+            save_enabled_libraries(enabled);
+            for library in manifest().libraries.iter().filter(|library| enabled.contains(&library.name)) {
+                if !requested_libraries.borrow_mut().insert(library.name.clone()) {
+                    continue;
+                }
+                let data = data.clone();
+                let requested_libraries = requested_libraries.clone();
+                let library_errors = library_errors.clone();
+                spawn_local(async move {
+                    match fetch_library_catalog(&page_url("catalog"), library).await {
+                        Ok(entries) => {
+                            library_errors.remove(&library.name);
+                            data.dispatch(DataActions::AddData(entries));
+                        }
+                        Err(e) => {
+                            tracing::error!("Error reading the catalog of {}: {}", library.name, e);
+                            // Unticking and ticking the library again retries it
+                            requested_libraries.borrow_mut().remove(&library.name);
+                            library_errors.insert(library.name.clone(), e);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    let onchange_library = {
+        let enabled = enabled.clone();
+        Callback::from(move |name: String| enabled.dispatch(EnabledLibrariesActions::Toggle(name)))
+    };
+
+    let fetch_errors = use_map(HashMap::<EntryKey, FetchError>::new());
+
+    let on_fetch_error = {
+        let fetch_errors = fetch_errors.clone();
+        Callback::from(move |(key, e): (EntryKey, Option<FetchError>)| {
+            match e {
+                Some(e) => {
+                    fetch_errors.insert(key, e);
+                }
+                None => {
+                    fetch_errors.remove(&key);
+                }
+            }
+        })
+    };
+
+    let is_y_log = use_state(|| initial_query.is_y_log());
+    let is_x_log = use_state(|| initial_query.is_x_log());
+    let trace_order = use_state(|| initial_query.trace_order());
+
+    let query = {
+        let mut query = PlotQuery {
+            xlog: Some(u8::from(*is_x_log)),
+            ylog: Some(u8::from(*is_y_log)),
+            element: element_search.clone(),
+            nucleons: nucleons_search.clone(),
+            reaction: reaction_search.clone(),
+            mt: mt_search.clone(),
+            library: library_search.clone(),
+            projectile: projectile_search.clone(),
+            temperature: temperature_search.clone(),
+            ..PlotQuery::default()
+        };
+        query.set_keys(selection.keys());
+        query.set_libraries(&enabled);
+        query.set_trace_order(*trace_order);
+        query
+    };
+
+    use_effect_with(query, move |query| {
+        if let Some(navigator) = navigator {
+            if let Err(e) = navigator.replace_with_query(&Route::Plot, query) {
+                tracing::error!("Error updating the URL: {:?}", e);
+            }
+        }
+    });
+
+    let local_files = use_state(|| None::<Rc<PickedFiles>>);
 
     let onclick_toggle_y_log = {
         let is_y_log = is_y_log.clone();
@@ -269,6 +243,16 @@ pub fn home() -> Html {
         })
     };
 
+    let onclick_toggle_trace_order = {
+        let trace_order = trace_order.clone();
+        Callback::from(move |_| {
+            trace_order.set(match *trace_order {
+                TraceOrder::Selection => TraceOrder::Label,
+                TraceOrder::Label => TraceOrder::Selection,
+            });
+        })
+    };
+
     let onclick_toggle_x_log = {
         let is_x_log = is_x_log.clone();
         Callback::from(move |_| {
@@ -285,7 +269,7 @@ pub fn home() -> Html {
         // ColumnBuilder::new("library").orderable(true).short_name("Library").data_property("library").header_class("user-select-none").build(),
         ColumnBuilder::new("mt").orderable(true).short_name("MT").data_property("mt").header_class("user-select-none").build(),
         ColumnBuilder::new("library").orderable(true).short_name("Library").data_property("library").header_class("user-select-none").build(),
-        // ColumnBuilder::new("temperature").orderable(true).short_name("Temperature").data_property("temperature").header_class("user-select-none").build(),
+        ColumnBuilder::new("temperature").orderable(true).short_name("Temperature").data_property("temperature").header_class("user-select-none").build(),
     ];
 
     let options = Options {
@@ -296,142 +280,48 @@ pub fn home() -> Html {
     };
 
     let clear_plot_callback = {
-        let selected_ids = selected_ids.clone();
+        let selection = selection.clone();
+        let fetch_errors = fetch_errors.clone();
         Callback::from(move |_: MouseEvent| {
-            selected_ids.clear();
+            selection.dispatch(SelectionActions::Clear);
+            fetch_errors.clear();
         })
     };
 
     let callback_sum = {
-        let selected_ids = selected_ids.clone();
-        Callback::from(move |id: i32| {
-            if !selected_ids.insert(id) {
-                selected_ids.remove(&id);
+        let selection = selection.clone();
+        let fetch_errors = fetch_errors.clone();
+        Callback::from(move |key: EntryKey| {
+            if selection.contains(&key) {
+                fetch_errors.remove(&key);
             }
+            selection.dispatch(SelectionActions::Toggle(key));
         })
     };
 
-    let filtered_data: Vec<TableLine> = {
-        // First pass: check for exact matches across all entries
-        let has_element_exact = match element_search {
-            Some(ref term) => {
-                let term_lower = term.to_lowercase();
-                mock_data.data.iter().any(|entry| entry.element.to_lowercase() == term_lower)
-            }
-            None => true,
-        };
-        let has_nucleons_exact = match nucleons_search {
-            Some(ref term) => {
-                mock_data.data.iter().any(|entry| entry.nucleons.to_string() == *term)
-            }
-            None => true,
-        };
-        let has_reaction_exact = match reaction_search {
-            Some(ref term) => {
-                let term_lower = term.to_lowercase();
-                mock_data.data.iter().any(|entry| entry.reaction.to_lowercase() == term_lower)
-            }
-            None => true,
-        };
-        let has_mt_exact = match mt_search {
-            Some(ref term) => {
-                mock_data.data.iter().any(|entry| entry.mt.to_string() == *term)
-            }
-            None => true,
-        };
-        let has_library_exact = match library_search {
-            Some(ref term) => {
-                let term_lower = term.to_lowercase();
-                mock_data.data.iter().any(|entry| entry.library.to_lowercase() == term_lower)
-            }
-            None => true,
-        };
-    
-        // Second pass: filter based on match criteria
-        mock_data.data
-            .iter()
-            .enumerate()
-            .filter(|(_, entry)| {
-                let element = &entry.element;
-                let nucleons = &entry.nucleons;
-                let reaction = &entry.reaction;
-                let mt = &entry.mt;
-                let library = &entry.library;
-    
-                let element_match = match element_search {
-                    Some(ref term) => {
-                        let term_lower = term.to_lowercase();
-                        let element_lower = element.to_lowercase();
-                        if has_element_exact {
-                            element_lower == term_lower
-                        } else {
-                            element_lower.starts_with(&term_lower)
-                        }
-                    }
-                    None => true,
-                };
-                let nucleons_match = match nucleons_search {
-                    Some(ref term) => {
-                        let nucleons_str = nucleons.to_string();
-                        if has_nucleons_exact {
-                            nucleons_str == *term
-                        } else {
-                            nucleons_str.starts_with(term)
-                        }
-                    }
-                    None => true,
-                };
-                let reaction_match = match reaction_search {
-                    Some(ref term) => {
-                        let term_lower = term.to_lowercase();
-                        let reaction_lower = reaction.to_lowercase();
-                        if has_reaction_exact {
-                            reaction_lower == term_lower
-                        } else {
-                            reaction_lower.starts_with(&term_lower)
-                        }
-                    }
-                    None => true,
-                };
-                let mt_match = match mt_search {
-                    Some(ref term) => {
-                        let mt_str = mt.to_string();
-                        if has_mt_exact {
-                            mt_str == *term
-                        } else {
-                            mt_str.starts_with(term)
-                        }
-                    }
-                    None => true,
-                };
-                let library_match = match library_search {
-                    Some(ref term) => {
-                        let term_lower = term.to_lowercase();
-                        let library_lower = library.to_lowercase();
-                        if has_library_exact {
-                            library_lower == term_lower
-                        } else {
-                            library_lower.starts_with(&term_lower)
-                        }
-                    }
-                    None => true,
-                };
-    
-                element_match && nucleons_match && reaction_match && mt_match && library_match
-            })
-            .map(|(id, entry)| TableLine {
+    // Libraries the user registered are not in the manifest and are always listed
+    let filtered_data: Vec<TableLine> = search
+        .filter(mock_data)
+        .into_iter()
+        .filter(|entry| enabled.iter().any(|name| *name == *entry.library) || manifest().get(&entry.library).is_none())
+        .map(|entry| {
+            let key = entry.key();
+            TableLine {
                 id: entry.id,
-                element: entry.element.clone(),
-                nucleons: entry.nucleons.clone(),
-                reaction: entry.reaction.clone(),
-                mt: entry.mt.clone(),
-                library: entry.library.clone(),
-                temperature: entry.temperature.clone(),
-                checked: selected_ids.current().contains(&(id as i32)),
+                element: entry.element.to_string(),
+                nucleons: entry.nucleons,
+                isomer: entry.isomer,
+                reaction: entry.reaction.to_string(),
+                mt: entry.mt,
+                library: entry.library.to_string(),
+                temperature: entry.temperature.to_string(),
+                checked: selection.contains(&key),
+                error: fetch_errors.current().get(&key).map(|e| e.to_string()),
+                key,
                 sum_callback: callback_sum.clone(),
-            })
-            .collect()
-    };
+            }
+        })
+        .collect();
 
     let limit = 10;
     let current_page = if filtered_data.is_empty() {
@@ -511,17 +401,165 @@ pub fn home() -> Html {
         })
     };
 
+    let oninput_projectile_search = {
+        let projectile_search_term = projectile_search_term.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if input.value().is_empty() {
+                projectile_search_term.set(None);
+            } else {
+                projectile_search_term.set(Some(input.value()));
+            }
+        })
+    };
+
+    let oninput_temperature_search = {
+        let temperature_search_term = temperature_search_term.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if input.value().is_empty() {
+                temperature_search_term.set(None);
+            } else {
+                temperature_search_term.set(Some(input.value()));
+            }
+        })
+    };
+
+    // The selected reactions at the temperatures not selected yet, to compare their Doppler broadening.
+    // Each selected key is looked for in the whole catalog, so only when either changes
+    let other_temperatures = {
+        let data = data.clone();
+        use_memo((data.generation(), (*selection).clone()), move |(_, selection)| {
+            selection
+                .iter()
+                .flat_map(|key| data.at_all_temperatures(key))
+                .map(|entry| entry.key())
+                .filter(|key| !selection.contains(key))
+                .collect::<Vec<EntryKey>>()
+        })
+    };
+
+    let onclick_add_temperatures = {
+        let selection = selection.clone();
+        let other_temperatures = other_temperatures.clone();
+        Callback::from(move |_| {
+            selection.dispatch(SelectionActions::Extend((*other_temperatures).clone()));
+        })
+    };
+
+    // The selected reactions in the libraries not selected yet, to compare evaluations
+    let other_libraries = {
+        let data = data.clone();
+        use_memo((data.generation(), (*selection).clone()), move |(_, selection)| {
+            selection
+                .iter()
+                .flat_map(|key| data.in_all_libraries(key))
+                .map(|entry| entry.key())
+                .filter(|key| !selection.contains(key))
+                .collect::<Vec<EntryKey>>()
+        })
+    };
+
+    let onclick_add_libraries = {
+        let selection = selection.clone();
+        let other_libraries = other_libraries.clone();
+        Callback::from(move |_| {
+            selection.dispatch(SelectionActions::Extend((*other_libraries).clone()));
+        })
+    };
+
+    let export_format = use_state(ExportFormat::default);
+
+    let onchange_export_format = {
+        let export_format = export_format.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Ok(format) = select.value().parse() {
+                export_format.set(format);
+            }
+        })
+    };
 
     let onclick_download = {
-        let selected_ids = selected_ids.clone();
+        let selection = selection.clone();
+        let data = data.clone();
+        let export_format = export_format.clone();
+        let local_files = local_files.clone();
+        let on_fetch_error = on_fetch_error.clone();
         Callback::from(move |_| {
-            let selected_ids = selected_ids.current().clone();
+            let selection = selection.0.clone();
+            let data = data.clone();
+            let format = *export_format;
+            let local_files = (*local_files).clone();
+            let on_fetch_error = on_fetch_error.clone();
             spawn_local(async move {
-                download_xs_cache(selected_ids).await;
+                download_xs_cache(selection, data, format, local_files, on_fetch_error).await;
             });
         })
     };
 
+    let onchange_local_files = {
+        let local_files = local_files.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(file_list) = input.files() {
+                let picked = PickedFiles::from_file_list(&file_list);
+                if picked.files.is_empty() {
+                    local_files.set(None);
+                } else {
+                    local_files.set(Some(Rc::new(picked)));
+                }
+            }
+        })
+    };
+
+    let onclick_use_online = {
+        let local_files = local_files.clone();
+        Callback::from(move |_| {
+            local_files.set(None);
+        })
+    };
+
+    // Libraries the user registered are not in the bundled catalog, so read their indices once
+    {
+        let data = data.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let (entries, failures) = fetch_missing_libraries(&data).await;
+                for (library, e) in failures {
+                    tracing::error!("Error reading the index of {}: {}", library, e);
+                }
+                if !entries.is_empty() {
+                    data.dispatch(DataActions::AddData(entries));
+                }
+            });
+        });
+    }
+
+    let has_user_libraries = user_libraries().is_some();
+
+    let onchange_libraries_file = Callback::from(move |e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        spawn_local(async move {
+            let text = JsFuture::from(file.text()).await.ok().and_then(|text| text.as_string()).unwrap_or_default();
+            let window = web_sys::window().unwrap();
+            match save_user_libraries(&text) {
+                Ok(()) => window.location().reload().unwrap(),
+                Err(e) => {
+                    let _ = window.alert_with_message(&format!("{} is not a valid library registry: {}", file.name(), e));
+                }
+            }
+        });
+    });
+
+    let onclick_reset_libraries = Callback::from(move |_| {
+        clear_user_libraries();
+        web_sys::window().unwrap().location().reload().unwrap();
+    });
+
     // let pagination_options = yew_custom_components::pagination::Options::default()
     //     .show_prev_next(true)
     //     .show_first_last(true)
@@ -545,6 +583,48 @@ pub fn home() -> Html {
             <h5>{"A searchable database of neutron cross sections with interactive plotting by Jon Shimwell. See my other projects on "}<a href="https://xsplot.com/" target="_blank">{"xsplot.com"}</a></h5>
 
 
+            if let Some(notice) = (*legacy_notice).clone() {
+                <div class="alert alert-warning alert-dismissible" role="alert">
+                    <i class="fas fa-triangle-exclamation me-2"></i>{notice}
+                    <button type="button" class="btn-close" aria-label="Close" onclick={
+                        let legacy_notice = legacy_notice.clone();
+                        move |_| legacy_notice.set(None)
+                    }></button>
+                </div>
+            }
+
+            <div class="d-flex mb-2 p-2 flex-wrap align-items-center">
+                <span class="me-3">{"Libraries:"}</span>
+                { for manifest().libraries.iter().map(|library| {
+                    let name = library.name.clone();
+                    let is_enabled = enabled.contains(&name);
+                    let error = library_errors.current().get(&name).map(|e| e.to_string());
+                    let is_loading = is_enabled && error.is_none() && !mock_data.has_library(&name);
+                    let label = registry().get(&name).map(|registered| registered.display_name().to_string()).unwrap_or_else(|| name.clone());
+                    let onchange = {
+                        let onchange_library = onchange_library.clone();
+                        let name = name.clone();
+                        move |_| onchange_library.emit(name.clone())
+                    };
+                    html! {
+                        <div class="form-check form-check-inline">
+                            <input class="form-check-input" type="checkbox" id={format!("library-{}", name)} checked={is_enabled} {onchange} />
+                            <label class="form-check-label" for={format!("library-{}", name)}>
+                                {format!("{} ({})", label, library.entries)}
+                                if is_loading {
+                                    <span class="spinner-border spinner-border-sm ms-2" role="status" title="Loading"></span>
+                                }
+                                if let Some(error) = error {
+                                    <span class="badge text-bg-danger ms-2" title={error}>
+                                        <i class="fas fa-triangle-exclamation me-1"></i>{"Failed"}
+                                    </span>
+                                }
+                            </label>
+                        </div>
+                    }
+                }) }
+            </div>
+
             <div class="d-flex mb-2">
                 <div class="flex-grow-1 p-2 input-group me-2">
                     <span class="input-group-text">
@@ -554,11 +634,12 @@ pub fn home() -> Html {
                         class="form-control" 
                         type="text" 
                         id="element-search" 
+                        value={element_search.clone().unwrap_or_default()}
                         placeholder="Search by element" 
                         oninput={oninput_element_search} 
                     />
                 </div>
-                <div class="flex-grow-1 p-2 input-group">
+                <div class="flex-grow-1 p-2 input-group me-2">
                     <span class="input-group-text">
                         <i class="fas fa-search"></i>
                     </span>
@@ -566,13 +647,40 @@ pub fn home() -> Html {
                         class="form-control" 
                         type="text" 
                         id="nucleon-search" 
+                        value={nucleons_search.clone().unwrap_or_default()}
                         placeholder="Search by nucleons" 
                         oninput={oninput_nucleon_search} 
                     />
                 </div>
+                <div class="flex-grow-1 p-2 input-group">
+                    <span class="input-group-text">
+                        <i class="fas fa-search"></i>
+                    </span>
+                    <input 
+                        class="form-control" 
+                        type="text" 
+                        id="temperature-search" 
+                        value={temperature_search.clone().unwrap_or_default()}
+                        placeholder="Search by temperature (K)" 
+                        oninput={oninput_temperature_search} 
+                    />
+                </div>
             </div>
             
             <div class="d-flex mb-2">
+                <div class="flex-grow-1 p-2 input-group me-2">
+                    <span class="input-group-text">
+                        <i class="fas fa-search"></i>
+                    </span>
+                    <input 
+                        class="form-control" 
+                        type="text" 
+                        id="projectile-search" 
+                        value={projectile_search.clone().unwrap_or_default()}
+                        placeholder="Search by projectile" 
+                        oninput={oninput_projectile_search} 
+                    />
+                </div>
                 <div class="flex-grow-1 p-2 input-group me-2">
                     <span class="input-group-text">
                         <i class="fas fa-search"></i>
@@ -581,6 +689,7 @@ pub fn home() -> Html {
                         class="form-control" 
                         type="text" 
                         id="reaction-search" 
+                        value={reaction_search.clone().unwrap_or_default()}
                         placeholder="Search by reaction" 
                         oninput={oninput_reaction_search} 
                     />
@@ -593,6 +702,7 @@ pub fn home() -> Html {
                         class="form-control" 
                         type="text" 
                         id="mt-search" 
+                        value={mt_search.clone().unwrap_or_default()}
                         placeholder="Search by MT" 
                         oninput={oninput_mt_search} 
                     />
@@ -605,6 +715,7 @@ pub fn home() -> Html {
                         class="form-control" 
                         type="text" 
                         id="library-search" 
+                        value={library_search.clone().unwrap_or_default()}
                         placeholder="Search by library" 
                         oninput={oninput_library_search} 
                     />
@@ -633,13 +744,92 @@ pub fn home() -> Html {
                     {if *is_y_log { "Switch Y to Linear Scale" } else { "Switch Y to Log Scale" }}
                 </button>
 
-                <button 
-                    class="btn btn-primary me-2"
-                    onclick={onclick_download}
+                <button
+                onclick={onclick_toggle_trace_order}
+                class="btn btn-primary me-2"
+                >
+                    {match *trace_order {
+                        TraceOrder::Selection => "Order Legend by Name",
+                        TraceOrder::Label => "Order Legend by Selection",
+                    }}
+                </button>
+
+                <button
+                onclick={onclick_add_temperatures}
+                class="btn btn-primary me-2"
+                disabled={other_temperatures.is_empty()}
+                title="Also plot the selected reactions at every other temperature they are available at"
                 >
-                    <i class="fas fa-download me-2"></i>
-                    {" Download Cross Section Data"}
+                    { "Add Other Temperatures" }
                 </button>
+
+                <button
+                onclick={onclick_add_libraries}
+                class="btn btn-primary me-2"
+                disabled={other_libraries.is_empty()}
+                title="Also plot the selected reactions from every other library that evaluates them"
+                >
+                    { "Add Other Libraries" }
+                </button>
+
+                <div class="input-group w-auto me-2">
+                    <button 
+                        class="btn btn-primary"
+                        onclick={onclick_download}
+                    >
+                        <i class="fas fa-download me-2"></i>
+                        {" Download Cross Section Data"}
+                    </button>
+                    <select class="form-select" title="Format of the download" onchange={onchange_export_format}>
+                        { for ExportFormat::ALL.iter().map(|format| html! {
+                            <option value={format.to_string()} selected={*format == *export_format}>{format.description()}</option>
+                        }) }
+                    </select>
+                </div>
+
+                <label class="btn btn-secondary me-2" title="Pick a directory of JSON files to plot without internet access">
+                    <i class="fas fa-folder-open me-2"></i>
+                    {match &*local_files {
+                        Some(files) => format!(" Local Files ({})", files.files.len()),
+                        None => " Use Local Files".to_string(),
+                    }}
+                    <input
+                        class="d-none"
+                        type="file"
+                        multiple=true
+                        webkitdirectory=true
+                        onchange={onchange_local_files}
+                    />
+                </label>
+
+                if local_files.is_some() {
+                    <button
+                    onclick={onclick_use_online}
+                    class="btn btn-secondary me-2"
+                    >
+                        { "Use Online Data" }
+                    </button>
+                }
+
+                <label class="btn btn-secondary me-2" title="Pick a libraries.json adding or changing evaluated libraries, kept in this browser">
+                    <i class="fas fa-book me-2"></i>
+                    {" Load Libraries"}
+                    <input
+                        class="d-none"
+                        type="file"
+                        accept=".json,application/json"
+                        onchange={onchange_libraries_file}
+                    />
+                </label>
+
+                if has_user_libraries {
+                    <button
+                    onclick={onclick_reset_libraries}
+                    class="btn btn-secondary me-2"
+                    >
+                        { "Reset Libraries" }
+                    </button>
+                }
                 
             </div>
                 
@@ -655,7 +845,7 @@ pub fn home() -> Html {
                     data={paginated_data} 
                     orderable={true}
                 />
-                <h5>{sum}{" / 41337"}</h5>
+                <h5>{sum}{" / "}{mock_data.len()}</h5>
                 </div>
                 <div class="flex-grow-1 p-2 input-group">
 
@@ -668,10 +858,14 @@ pub fn home() -> Html {
                 // />
                 <div class="flex-grow-1 p-2 input-group me-2">
                     <PlotComponent
-                        selected_ids={(*selected_ids.current()).clone()}
+                        selection={selection.0.clone()}
+                        data={data.clone()}
+                        trace_order={*trace_order}
                         is_y_log={is_y_log.clone()}
                         is_x_log={is_x_log.clone()}
                         clear_plot_callback={clear_plot_callback.clone()}
+                        local_files={(*local_files).clone()}
+                        on_fetch_error={on_fetch_error.clone()}
                     />
                 </div>
                 // <h5>{"Created by Jon Shimwell, source code available "}</h5>
@@ -689,19 +883,22 @@ pub fn home() -> Html {
 struct TableLine {
     pub checked: bool,
     pub id: i32,
+    pub key: EntryKey,
     pub element: String,
     pub nucleons: i32,
+    pub isomer: u8,
     pub reaction: String,
     pub mt: i32,
     pub library: String,
     pub temperature: String,
+    pub error: Option<String>,
     #[serde(skip_serializing)]
-    pub sum_callback: Callback<i32>,
+    pub sum_callback: Callback<EntryKey>,
 }
 
 impl PartialEq<Self> for TableLine {
     fn eq(&self, other: &Self) -> bool {
-        self.element == other.element && self.nucleons == other.nucleons && self.library == other.library && self.reaction == other.reaction  && self.mt == other.mt && self.checked == other.checked
+        self.element == other.element && self.nucleons == other.nucleons && self.isomer == other.isomer && self.library == other.library && self.reaction == other.reaction  && self.mt == other.mt && self.temperature == other.temperature && self.checked == other.checked && self.error == other.error
     }
 }
 
@@ -714,19 +911,31 @@ impl PartialOrd for TableLine {
 impl TableData for TableLine {
     fn get_field_as_html(&self, field_name: &str) -> yew_custom_components::table::error::Result<Html> {
         match field_name {
-            "select" => Ok(html!( <input type="checkbox" style="width: 30px; height: 30px;" checked={self.checked}
+            "select" => Ok(html!(
+                <>
+                <input type="checkbox" style="width: 30px; height: 30px;" checked={self.checked}
                 onclick={
-                let id = self.id;
+                let key = self.key.clone();
                 let handle_sum = self.sum_callback.clone();
-                move |_| { handle_sum.emit(id); }
-                } /> )
-            ),
+                move |_| { handle_sum.emit(key.clone()); }
+                } />
+                if let Some(error) = &self.error {
+                    <span class="badge text-bg-danger ms-2" title={error.clone()}>
+                        <i class="fas fa-triangle-exclamation me-1"></i>{"Failed"}
+                    </span>
+                }
+                </>
+            )),
             "id" => Ok(html! { self.id }),
             "element" => Ok(html! { self.element.clone() }),
-            "nucleons" => Ok(html! { self.nucleons }),
+            "nucleons" => Ok(match self.isomer {
+                0 => html! { self.nucleons },
+                isomer => html! { format!("{}m{}", self.nucleons, isomer) },
+            }),
             "library" => Ok(html! { self.library.clone() }),
             "reaction" => Ok(html! { self.reaction.clone() }),
             "mt" => Ok(html! { self.mt }),
+            "temperature" => Ok(html! { format!("{} K", self.temperature) }),
             _ => Ok(html! {}),
         }
     }
@@ -739,6 +948,7 @@ impl TableData for TableLine {
             "library" => Ok(serde_value::Value::String(self.library.clone())),
             "reaction" => Ok(serde_value::Value::String(self.reaction.clone())),
             "mt" => Ok(serde_value::Value::I32(self.mt)),
+            "temperature" => Ok(serde_value::Value::F64(self.temperature.parse().unwrap_or_default())),
             "select" => Ok(serde_value::Value::Bool(self.checked)),
             _ => Ok(serde_value::to_value(()).unwrap()),
         }
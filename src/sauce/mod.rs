@@ -3,11 +3,16 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 
 mod home;
+mod plot;
+mod query;
+mod state;
 
 #[derive(Routable, PartialEq, Eq, Clone, Debug)]
 pub enum Route {
     #[at("/")]
     Home,
+    #[at("/plot")]
+    Plot,
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -25,17 +30,13 @@ pub fn document() -> Option<web_sys::Document> {
 pub fn app() -> Html {
     let ctx = use_state(|| crate::types::theme::Theme { dark: true });
 
-    document().and_then(|doc| {
-        doc.document_element().and_then(|el| {
-            if ctx.get_dark() {
-                el.set_attribute("data-bs-theme", "dark").unwrap();
-            } else {
-                el.set_attribute("data-bs-theme", "danger").unwrap();
-            };
-            Some(())
-        });
-        Some(())
-    });
+    if let Some(el) = document().and_then(|doc| doc.document_element()) {
+        if ctx.get_dark() {
+            el.set_attribute("data-bs-theme", "dark").unwrap();
+        } else {
+            el.set_attribute("data-bs-theme", "danger").unwrap();
+        };
+    }
 
     html! {
         <ContextProvider<UseStateHandle<crate::types::theme::Theme>> context={ctx}>
@@ -54,6 +55,7 @@ pub fn app() -> Html {
 fn switch(routes: Route) -> Html {
     match routes {
         Route::Home => html!( <home::Home /> ),
+        Route::Plot => html!( <home::Home /> ),
         Route::NotFound => html!( <home::Home /> ),
     }
 }
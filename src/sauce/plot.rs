@@ -0,0 +1,434 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use futures::stream::{self, StreamExt};
+use js_sys::{Array, Uint8Array};
+use plotly::{Plot, Scatter, Trace};
+use plotly::common::Line;
+use tracing::error;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{Blob, BlobPropertyBag, HtmlElement, HtmlInputElement, HtmlSelectElement, Url};
+use yew::prelude::*;
+use xsplot::cache::Cached;
+use xsplot::colours::{ColourMap, PALETTE};
+use xsplot::error::FetchError;
+use xsplot::catalog::EntryKey;
+use xsplot::fetch::{fetch_plotly_js, generate_cache, get_values_by_key, MAX_CONCURRENT_FETCHES};
+use xsplot::figure::{figure, layout, to_html, FigureFormat, FigureStyle};
+use xsplot::library::registry;
+use xsplot::matplotlib::to_matplotlib;
+use xsplot::selection::Selection;
+use xsplot::source::{DataSource, GitHub, PickedFiles};
+use super::state::CatalogState;
+
+const PLOT_ID: &str = "plot-div";
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_namespace = Plotly, js_name = addTraces)]
+    async fn add_traces(id: &str, traces: &JsValue, new_indices: &JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, js_namespace = Plotly, js_name = deleteTraces)]
+    async fn delete_traces(id: &str, indices: &JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, js_namespace = Plotly, js_name = relayout)]
+    async fn relayout(id: &str, layout: &JsValue) -> Result<JsValue, JsValue>;
+
+    /// Resolves to a data URL of the figure drawn as an image.
+    #[wasm_bindgen(catch, js_namespace = Plotly, js_name = toImage)]
+    async fn to_image(figure: &JsValue, options: &JsValue) -> Result<JsValue, JsValue>;
+}
+
+static ONLINE: Cached<GitHub> = Cached::new(GitHub);
+
+/// The user's picked files when there are some, the cached GitHub repositories otherwise.
+pub fn data_source(local_files: &Option<Rc<PickedFiles>>) -> &dyn DataSource {
+    match local_files {
+        Some(files) => files.as_ref(),
+        None => &ONLINE,
+    }
+}
+
+/// Saves `href` as a file through a hidden link.
+pub fn save_as(href: &str, file_name: &str) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let a = document.create_element("a").unwrap();
+    a.set_attribute("href", href).unwrap();
+    a.set_attribute("download", file_name).unwrap();
+    a.set_attribute("style", "display: none;").unwrap();
+    document.body().unwrap().append_child(&a).unwrap();
+    a.dyn_ref::<HtmlElement>().unwrap().click();
+    document.body().unwrap().remove_child(&a).unwrap();
+}
+
+/// Saves `contents` as a file through a blob URL.
+pub fn save_file(contents: &[u8], mime_type: &str, file_name: &str) {
+    let blob_options = BlobPropertyBag::new();
+    blob_options.set_type(mime_type);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&Array::of1(&Uint8Array::from(contents)), &blob_options).unwrap();
+    let url = Url::create_object_url_with_blob(&blob).unwrap();
+    save_as(&url, file_name);
+    Url::revoke_object_url(&url).unwrap();
+}
+
+/// How the traces, and so the legend, are ordered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraceOrder {
+    /// The order the reactions were selected in
+    #[default]
+    Selection,
+    /// Alphabetically by label
+    Label,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PlotProps {
+    pub selection: Selection,
+    /// The catalog as loaded, which the selected keys are looked up in
+    pub data: UseReducerHandle<CatalogState>,
+    pub trace_order: TraceOrder,
+    pub is_y_log: UseStateHandle<bool>,
+    pub is_x_log: UseStateHandle<bool>,
+    pub clear_plot_callback: Callback<MouseEvent>,
+    pub local_files: Option<Rc<PickedFiles>>,
+    /// Told about reactions that failed to load, and with `None` once one of them loads after all
+    pub on_fetch_error: Callback<(EntryKey, Option<FetchError>)>,
+}
+
+/// What the plot div shows and what it should show.
+///
+/// The traces in the div are only ever added or removed one at a time, so
+/// `keys` and `labels` mirror plotly's trace order. The wanted selection and
+/// axis scales are refreshed on every render, letting a slow download check
+/// whether its trace is still wanted once it arrives.
+#[derive(Default)]
+struct PlotState {
+    initialised: bool,
+    keys: Vec<EntryKey>,
+    labels: Vec<String>,
+    pending: HashSet<EntryKey>,
+    failed: HashSet<EntryKey>,
+    /// Kept across redraws so a reaction's colour never depends on what else is plotted
+    colours: ColourMap<EntryKey>,
+    /// Bumped when the plot is redrawn from scratch, so downloads started before are dropped
+    generation: u32,
+    selected: Selection,
+    trace_order: TraceOrder,
+    is_x_log: bool,
+    is_y_log: bool,
+}
+
+impl PlotState {
+    /// Where a new trace goes so the plotted ones stay in `trace_order`.
+    fn insert_position(&self, key: &EntryKey, label: &str) -> usize {
+        match self.trace_order {
+            TraceOrder::Selection => {
+                let rank = |key: &EntryKey| self.selected.iter().position(|selected| selected == key);
+                self.keys.iter().take_while(|&plotted| rank(plotted) < rank(key)).count()
+            }
+            TraceOrder::Label => self.labels.iter().take_while(|plotted| plotted.as_str() <= label).count(),
+        }
+    }
+}
+
+#[function_component(PlotComponent)]
+pub fn plot_component(props: &PlotProps) -> Html {
+    let state = use_mut_ref(PlotState::default);
+    {
+        let mut state = state.borrow_mut();
+        state.selected = props.selection.clone();
+        state.trace_order = props.trace_order;
+        state.is_x_log = *props.is_x_log;
+        state.is_y_log = *props.is_y_log;
+    }
+
+    {
+        let state = state.clone();
+        let data = props.data.clone();
+        let on_fetch_error = props.on_fetch_error.clone();
+        use_effect_with((props.local_files.clone(), props.trace_order), move |(local_files, _)| {
+            {
+                // A different source may hold different data and a different
+                // order needs every trace moved, so start over from the cache
+                let mut state = state.borrow_mut();
+                state.initialised = false;
+                state.keys.clear();
+                state.labels.clear();
+                state.pending.clear();
+                state.generation += 1;
+            }
+            spawn_local(sync_traces(state, data, local_files.clone(), on_fetch_error));
+        });
+    }
+
+    {
+        let state = state.clone();
+        let local_files = props.local_files.clone();
+        let on_fetch_error = props.on_fetch_error.clone();
+        // Also run once a library's entries arrive, plotting the keys that waited for them
+        use_effect_with((props.selection.clone(), props.data.clone()), move |(_, data)| {
+            spawn_local(sync_traces(state, data.clone(), local_files, on_fetch_error));
+        });
+    }
+
+    {
+        let state = state.clone();
+        use_effect_with((*props.is_x_log, *props.is_y_log), move |_| {
+            spawn_local(async move {
+                if state.borrow().initialised {
+                    update_layout(&state).await;
+                }
+            });
+        });
+    }
+
+    let figure_style = use_state(FigureStyle::default);
+
+    let onclick_download_figure = {
+        let state = state.clone();
+        let figure_style = figure_style.clone();
+        let data = props.data.clone();
+        let local_files = props.local_files.clone();
+        let on_fetch_error = props.on_fetch_error.clone();
+        Callback::from(move |_| {
+            spawn_local(download_figure(state.clone(), (*figure_style).clone(), data.clone(), local_files.clone(), on_fetch_error.clone()));
+        })
+    };
+
+    let onchange_figure_format = {
+        let figure_style = figure_style.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Ok(format) = select.value().parse() {
+                figure_style.set(FigureStyle { format, ..(*figure_style).clone() });
+            }
+        })
+    };
+
+    let style = &*figure_style;
+    html! {
+        <>
+            <div id={PLOT_ID}></div>
+            <div class="d-flex flex-wrap align-items-center gap-2 mt-2">
+                <div class="input-group w-auto">
+                    <button class="btn btn-primary" onclick={onclick_download_figure} title="Save the plotted reactions as an image, a web page or a Python script">
+                        <i class="fas fa-image me-2"></i>
+                        {" Download Figure"}
+                    </button>
+                    <select class="form-select" title="Format of the figure" onchange={onchange_figure_format}>
+                        { for FigureFormat::ALL.iter().map(|format| html! {
+                            <option value={format.to_string()} selected={*format == style.format}>{format.description()}</option>
+                        }) }
+                    </select>
+                </div>
+                { style_input("Width", "px", style.width, &figure_style, |style, value| style.width = value) }
+                { style_input("Height", "px", style.height, &figure_style, |style, value| style.height = value) }
+                if style.format == FigureFormat::Png {
+                    { style_input("Resolution", "dpi", style.dpi, &figure_style, |style, value| style.dpi = value) }
+                }
+                { style_input("Font size", "px", style.font_size, &figure_style, |style, value| style.font_size = value) }
+                { style_input("Line width", "px", style.line_width, &figure_style, |style, value| style.line_width = value) }
+                { style_input("Font", "", style.font_family.clone(), &figure_style, |style, value| style.font_family = value) }
+            </div>
+        </>
+    }
+}
+
+/// A labelled input for one figure setting, ignoring what does not parse or give a drawable figure.
+fn style_input<T>(label: &str, unit: &str, value: T, figure_style: &UseStateHandle<FigureStyle>, update: fn(&mut FigureStyle, T)) -> Html
+where
+    T: ToString + std::str::FromStr + 'static,
+{
+    let onchange = {
+        let figure_style = figure_style.clone();
+        let current = value.to_string();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(value) = input.value().trim().parse() {
+                let mut style = (*figure_style).clone();
+                update(&mut style, value);
+                match style.validate() {
+                    Ok(()) => figure_style.set(style),
+                    Err(e) => {
+                        error!("Invalid figure style: {}", e);
+                        input.set_value(&current);
+                    }
+                }
+            } else {
+                input.set_value(&current);
+            }
+        })
+    };
+    let input_type = if unit.is_empty() { "text" } else { "number" };
+    html! {
+        <div class="input-group input-group-sm w-auto">
+            <span class="input-group-text">{label}</span>
+            <input class="form-control" style="width: 6em;" type={input_type} min="0" value={value.to_string()} {onchange}/>
+            if !unit.is_empty() {
+                <span class="input-group-text">{unit}</span>
+            }
+        </div>
+    }
+}
+
+/// Draws the plotted traces again, styled as chosen, and saves them as an
+/// image, a web page or a matplotlib script.
+///
+/// The traces are read from the source again, which the cache answers, so the
+/// figure has the plotted curves in the plotted order and colours.
+async fn download_figure(state: Rc<RefCell<PlotState>>, style: FigureStyle, data: UseReducerHandle<CatalogState>, local_files: Option<Rc<PickedFiles>>, on_fetch_error: Callback<(EntryKey, Option<FetchError>)>) {
+    if let Err(e) = style.validate() {
+        error!("Invalid figure style: {}", e);
+        return;
+    }
+    let (keys, is_x_log, is_y_log) = {
+        let state = state.borrow();
+        (state.keys.clone(), state.is_x_log, state.is_y_log)
+    };
+    let (cache, failures) = generate_cache(&keys, &data, data_source(&local_files)).await;
+    for (key, e) in failures {
+        on_fetch_error.emit((key, Some(e)));
+    }
+    let colours: Vec<&'static str> = {
+        let state = state.borrow();
+        cache.keys.iter().map(|key| state.colours.get(key).unwrap_or(PALETTE[0])).collect()
+    };
+
+    let plot = figure(&cache, &colours, is_x_log, is_y_log, &style);
+    let contents = match style.format {
+        FigureFormat::Svg | FigureFormat::Png => return save_image(&plot, &style).await,
+        FigureFormat::Html => {
+            // Written into the page so it opens offline, or linked when it cannot be downloaded
+            let plotly_js = fetch_plotly_js().await;
+            if let Err(e) = &plotly_js {
+                error!("Error downloading plotly.js: {}", e);
+            }
+            to_html(&plot, plotly_js.as_deref().ok())
+        }
+        FigureFormat::Python => match to_matplotlib(&plot) {
+            Ok(script) => script,
+            Err(e) => {
+                error!("Error writing script: {}", e);
+                return;
+            }
+        },
+    };
+    save_file(contents.as_bytes(), style.format.mime_type(), &style.format.file_name());
+}
+
+/// Saves the figure as drawn by plotly.js, at the style's resolution for PNGs.
+async fn save_image(plot: &Plot, style: &FigureStyle) {
+    let options = serde_json::json!({
+        "format": style.format.extension(),
+        "width": style.width,
+        "height": style.height,
+        "scale": style.scale(),
+    });
+    let figure = js_sys::JSON::parse(&plot.to_json()).unwrap();
+    let options = js_sys::JSON::parse(&options.to_string()).unwrap();
+    match to_image(&figure, &options).await {
+        Ok(url) => save_as(&url.as_string().unwrap_or_default(), &style.format.file_name()),
+        Err(e) => error!("Error drawing figure: {:?}", e),
+    }
+}
+
+/// Adds the traces that were selected and removes the ones that were deselected since the last call.
+///
+/// Reactions that fail to load are reported through `on_fetch_error` and tried
+/// again on the next call, the rest are plotted regardless. Reactions of a
+/// registered library whose entries are not in `data` yet are left for a later
+/// call, once they are.
+async fn sync_traces(state: Rc<RefCell<PlotState>>, data: UseReducerHandle<CatalogState>, local_files: Option<Rc<PickedFiles>>, on_fetch_error: Callback<(EntryKey, Option<FetchError>)>) {
+    let (removed, added, generation) = {
+        let mut state = state.borrow_mut();
+        let removed: Vec<usize> = (0..state.keys.len()).rev().filter(|&i| !state.selected.contains(&state.keys[i])).collect();
+        for &i in &removed {
+            state.keys.remove(i);
+            state.labels.remove(i);
+        }
+        let is_loading = |key: &EntryKey| data.get_by_key(key).is_none() && !data.has_library(&key.library) && registry().get(&key.library).is_some();
+        let added: Vec<EntryKey> = state
+            .selected
+            .iter()
+            .filter(|key| !state.keys.contains(key) && !state.pending.contains(key) && !is_loading(key))
+            .cloned()
+            .collect();
+        state.pending.extend(added.iter().cloned());
+        (removed, added, state.generation)
+    };
+
+    if !state.borrow().initialised {
+        state.borrow_mut().initialised = true;
+        let mut plot = Plot::new();
+        plot.set_layout(build_layout(&state.borrow()));
+        plotly::bindings::new_plot(PLOT_ID, &plot).await;
+    } else if !removed.is_empty() {
+        let indices: Array = removed.iter().map(|&i| JsValue::from(i as u32)).collect();
+        if let Err(e) = delete_traces(PLOT_ID, &indices).await {
+            error!("Error deleting traces: {:?}", e);
+        }
+    }
+
+    // Draw each trace as soon as its download finishes, whatever the order
+    let source = data_source(&local_files);
+    let data = &*data;
+    let mut arrivals = stream::iter(added)
+        .map(|key| async move {
+            let result = get_values_by_key(&key, data, source).await;
+            (key, result)
+        })
+        .buffer_unordered(MAX_CONCURRENT_FETCHES);
+    while let Some((key, result)) = arrivals.next().await {
+        let (trace, position) = {
+            let mut state = state.borrow_mut();
+            let state = &mut *state;
+            if state.generation != generation {
+                return;
+            }
+            state.pending.remove(&key);
+            let (energy, cross_section, label) = match result {
+                Ok(values) => values,
+                Err(e) => {
+                    error!("Error fetching {}: {}", key, e);
+                    state.failed.insert(key.clone());
+                    on_fetch_error.emit((key, Some(e)));
+                    continue;
+                }
+            };
+            if state.failed.remove(&key) {
+                on_fetch_error.emit((key.clone(), None));
+            }
+            if !state.selected.contains(&key) || state.keys.contains(&key) {
+                continue;
+            }
+            let position = state.insert_position(&key, &label);
+            let colour = state.colours.assign(&key, &state.keys);
+            state.keys.insert(position, key);
+            state.labels.insert(position, label.clone());
+            let trace = Scatter::new(energy, cross_section)
+                .name(&label)
+                .line(Line::new().color(colour));
+            (trace, position)
+        };
+        let traces = Array::of1(&js_sys::JSON::parse(&trace.to_json()).unwrap());
+        if let Err(e) = add_traces(PLOT_ID, &traces, &JsValue::from(position as u32)).await {
+            error!("Error adding trace: {:?}", e);
+        }
+    }
+
+    update_layout(&state).await;
+}
+
+async fn update_layout(state: &Rc<RefCell<PlotState>>) {
+    let layout = build_layout(&state.borrow());
+    let layout = js_sys::JSON::parse(&serde_json::to_string(&layout).unwrap()).unwrap();
+    if let Err(e) = relayout(PLOT_ID, &layout).await {
+        error!("Error updating layout: {:?}", e);
+    }
+}
+
+fn build_layout(state: &PlotState) -> plotly::Layout {
+    layout(&state.labels, state.is_x_log, state.is_y_log)
+}
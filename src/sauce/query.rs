@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use xsplot::catalog::{manifest, Data, EntryKey};
+use xsplot::library::registry;
+
+use super::plot::TraceOrder;
+
+/// The plot state kept in the URL so a link reproduces the same view,
+/// e.g. `#/plot?ids=Fe_56_FENDL-3.2c_n_102_294K&xlog=1&ylog=0&element=Fe`.
+///
+/// Every field is optional so hand-written and older links still load, with
+/// missing values falling back to the defaults of a fresh page.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlotQuery {
+    /// Selected entry keys, comma separated in selection order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xlog: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ylog: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub element: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nucleons: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reaction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projectile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<String>,
+    /// Catalog libraries listed in the table, comma separated, all when missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub libs: Option<String>,
+}
+
+impl PlotQuery {
+    /// The selected keys that are in the catalog, or in a registered library
+    /// whose catalog is still loading. Numeric ids are left to [`PlotQuery::legacy_ids`].
+    pub fn keys(&self, data: &Data) -> Vec<EntryKey> {
+        self.ids
+            .iter()
+            .flat_map(|ids| ids.split(','))
+            .filter(|id| id.trim().parse::<i32>().is_err())
+            .filter_map(|id| id.trim().parse::<EntryKey>().ok())
+            .filter(|key| data.get_by_key(key).is_some() || (!data.has_library(&key.library) && registry().get(&key.library).is_some()))
+            .collect()
+    }
+
+    /// The numeric ids of links made before entries had keys, which are
+    /// looked up in [`LegacyIds`](xsplot::catalog::LegacyIds) as the catalog
+    /// has been renumbered since.
+    pub fn legacy_ids(&self) -> Vec<i32> {
+        self.ids.iter().flat_map(|ids| ids.split(',')).filter_map(|id| id.trim().parse().ok()).collect()
+    }
+
+    pub fn set_keys(&mut self, keys: &[EntryKey]) {
+        self.ids = if keys.is_empty() {
+            None
+        } else {
+            Some(keys.iter().map(|key| key.to_string()).collect::<Vec<_>>().join(","))
+        };
+    }
+
+    pub fn libraries(&self) -> Option<Vec<String>> {
+        self.libs.as_ref().map(|libs| libs.split(',').filter(|name| !name.is_empty()).map(str::to_string).collect())
+    }
+
+    /// Leaves the libraries out of the link when they are all listed, so it stays short.
+    pub fn set_libraries(&mut self, names: &[String]) {
+        let all = manifest().libraries.iter().all(|library| names.contains(&library.name));
+        self.libs = if all { None } else { Some(names.join(",")) };
+    }
+
+    pub fn is_x_log(&self) -> bool {
+        self.xlog != Some(0)
+    }
+
+    pub fn is_y_log(&self) -> bool {
+        self.ylog != Some(0)
+    }
+
+    pub fn trace_order(&self) -> TraceOrder {
+        match self.order.as_deref() {
+            Some("label") => TraceOrder::Label,
+            _ => TraceOrder::Selection,
+        }
+    }
+
+    pub fn set_trace_order(&mut self, trace_order: TraceOrder) {
+        self.order = match trace_order {
+            TraceOrder::Selection => None,
+            TraceOrder::Label => Some("label".to_string()),
+        };
+    }
+}
@@ -0,0 +1,134 @@
+//! The catalog, the selection and the enabled libraries as yew state.
+//!
+//! The library's types know nothing of yew, so they are wrapped here to be
+//! reduced, and deref to the wrapped type everywhere else.
+use std::ops::Deref;
+use std::rc::Rc;
+
+use tracing::error;
+use yew::Reducible;
+use xsplot::catalog::{manifest, Data, Entry, EntryKey};
+use xsplot::selection::Selection;
+
+/// The catalog, growing as library catalog files and user libraries are read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogState(pub Data);
+
+impl Deref for CatalogState {
+    type Target = Data;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub enum DataActions {
+    /// Appends entries read at runtime, such as a library's catalog file, numbering them after the others
+    AddData(Vec<Entry>),
+}
+
+impl Reducible for CatalogState {
+    type Action = DataActions;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut new = (*self).clone();
+        match action {
+            DataActions::AddData(entries) => {
+                let first_id = new.0.next_id();
+                if let Err(e) = new.0.extend(entries.into_iter().enumerate().map(|(i, entry)| Entry { id: first_id + i as i32, ..entry })) {
+                    error!("Error adding to the catalog: {}", e);
+                    return self;
+                }
+            }
+        }
+        Rc::new(new)
+    }
+}
+
+/// The reactions picked in the table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelectionState(pub Selection);
+
+impl Deref for SelectionState {
+    type Target = Selection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub enum SelectionActions {
+    Toggle(EntryKey),
+    /// Adds the keys that are not selected yet, after the current ones
+    Extend(Vec<EntryKey>),
+    Clear,
+}
+
+impl Reducible for SelectionState {
+    type Action = SelectionActions;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut new = (*self).clone();
+        match action {
+            SelectionActions::Toggle(key) => {
+                new.0.toggle(key);
+            }
+            SelectionActions::Extend(keys) => {
+                for key in keys {
+                    new.0.insert(key);
+                }
+            }
+            SelectionActions::Clear => new.0.clear(),
+        }
+        Rc::new(new)
+    }
+}
+
+/// The catalog libraries listed in the table, in the order they were turned on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnabledLibraries(pub Vec<String>);
+
+impl EnabledLibraries {
+    /// Turns on the catalog libraries `names` that are not on yet, leaving
+    /// out user libraries, which have no catalog file to download.
+    pub fn include<'a>(&mut self, names: impl IntoIterator<Item = &'a String>) {
+        for name in names {
+            if manifest().get(name).is_some() && !self.0.contains(name) {
+                self.0.push(name.clone());
+            }
+        }
+    }
+}
+
+impl Deref for EnabledLibraries {
+    type Target = Vec<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub enum EnabledLibrariesActions {
+    /// Turns a library on or off
+    Toggle(String),
+    /// Turns on the libraries of reactions selected after the page loaded
+    Include(Vec<String>),
+}
+
+impl Reducible for EnabledLibraries {
+    type Action = EnabledLibrariesActions;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut new = (*self).clone();
+        match action {
+            EnabledLibrariesActions::Toggle(name) => match new.0.iter().position(|enabled| *enabled == name) {
+                Some(i) => {
+                    new.0.remove(i);
+                }
+                None => new.0.push(name),
+            },
+            EnabledLibrariesActions::Include(names) => new.include(&names),
+        }
+        Rc::new(new)
+    }
+}
@@ -0,0 +1,130 @@
+//! The search boxes above the table, narrowing the catalog down to the rows shown.
+use crate::catalog::{Data, Entry};
+use crate::reactions::projectile_symbol;
+
+/// One term per search box, `None` where the box is empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Search {
+    pub element: Option<String>,
+    pub nucleons: Option<String>,
+    pub reaction: Option<String>,
+    pub mt: Option<String>,
+    pub library: Option<String>,
+    /// Symbol or name of the incident particle, e.g. `p` or `proton`
+    pub projectile: Option<String>,
+    /// In kelvin, with or without the unit
+    pub temperature: Option<String>,
+}
+
+/// Matches one field, exactly when any entry has that exact value and by prefix otherwise,
+/// so MT `1` does not also list MT 16 or 102 while a partial term lists everything it starts.
+struct Term {
+    term: String,
+    exact: bool,
+}
+
+impl Term {
+    /// Case is ignored, which leaves the numeric fields unaffected.
+    fn new(term: &Option<String>, mut values: impl Iterator<Item = String>) -> Option<Self> {
+        let term = term.as_ref()?.to_lowercase();
+        let exact = values.any(|value| value.to_lowercase() == term);
+        Some(Self { term, exact })
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let value = value.to_lowercase();
+        if self.exact {
+            value == self.term
+        } else {
+            value.starts_with(&self.term)
+        }
+    }
+}
+
+impl Search {
+    /// The entries matching every term, in catalog order.
+    pub fn filter<'a>(&self, data: &'a Data) -> Vec<&'a Entry> {
+        let entries = || data.iter();
+        let element = Term::new(&self.element, entries().map(|entry| entry.element.to_string()));
+        let nucleons = Term::new(&self.nucleons, entries().map(|entry| entry.nucleons.to_string()));
+        let reaction = Term::new(&self.reaction, entries().map(|entry| entry.reaction.to_string()));
+        let mt = Term::new(&self.mt, entries().map(|entry| entry.mt.to_string()));
+        let library = Term::new(&self.library, entries().map(|entry| entry.library.to_string()));
+        let projectile = self.projectile.as_ref().map(|term| projectile_symbol(term.trim()).to_string());
+        let projectile = Term::new(&projectile, entries().map(|entry| projectile_symbol(&entry.projectile).to_string()));
+        let temperature = self.temperature.as_ref().map(|term| term.trim().trim_end_matches(['K', 'k']).trim().to_string());
+        let temperature = Term::new(&temperature, entries().map(|entry| entry.temperature.to_string()));
+
+        entries()
+            .filter(|entry| {
+                element.as_ref().is_none_or(|term| term.matches(&entry.element))
+                    && nucleons.as_ref().is_none_or(|term| term.matches(&entry.nucleons.to_string()))
+                    && reaction.as_ref().is_none_or(|term| term.matches(&entry.reaction))
+                    && mt.as_ref().is_none_or(|term| term.matches(&entry.mt.to_string()))
+                    && library.as_ref().is_none_or(|term| term.matches(&entry.library))
+                    && projectile.as_ref().is_none_or(|term| term.matches(projectile_symbol(&entry.projectile)))
+                    && temperature.as_ref().is_none_or(|term| term.matches(&entry.temperature))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::load_data_from_csv;
+
+    const CATALOG: &str = "id,element,nucleons,library,incident_particle,mt,temperature
+0,Fe,54,ENDFB-8.0,n,102,294
+1,Li,6,FENDL-3.2c,n,105,294
+2,Fe,56,FENDL-3.2c,n,16,294
+3,Fe,56,FENDL-3.2c,n,2,0
+4,Fe,56,FENDL-3.2c,n,2,294
+5,Fe,56,TENDL-2019,p,16,294
+6,Li,7,TENDL-2019,h,4,294
+";
+
+    #[test]
+    fn exact_terms_take_precedence_over_prefixes() {
+        let data = Data::new(load_data_from_csv(CATALOG).unwrap()).unwrap();
+        let mt_1 = Search { mt: Some("1".to_string()), ..Search::default() };
+        let mts: Vec<i32> = mt_1.filter(&data).iter().map(|entry| entry.mt).collect();
+        assert_eq!(mts, [102, 105, 16, 16]);
+
+        let mt_16 = Search { mt: Some("16".to_string()), ..Search::default() };
+        let mts: Vec<i32> = mt_16.filter(&data).iter().map(|entry| entry.mt).collect();
+        assert_eq!(mts, [16, 16]);
+
+        let iron = Search { element: Some("fe".to_string()), ..Search::default() };
+        assert_eq!(iron.filter(&data).len(), 5);
+    }
+
+    #[test]
+    fn temperatures_match_with_or_without_unit() {
+        let data = Data::new(load_data_from_csv(CATALOG).unwrap()).unwrap();
+        for term in ["0", "0K", "0 k"] {
+            let cold = Search { temperature: Some(term.to_string()), ..Search::default() };
+            let ids: Vec<i32> = cold.filter(&data).iter().map(|entry| entry.id).collect();
+            assert_eq!(ids, [3], "{}", term);
+        }
+
+        let elastic = data.at_all_temperatures(&data.get(4).unwrap().key());
+        let ids: Vec<i32> = elastic.iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, [3, 4]);
+    }
+
+    #[test]
+    fn projectiles_match_by_symbol_or_name() {
+        let data = Data::new(load_data_from_csv(CATALOG).unwrap()).unwrap();
+        for term in ["p", "proton"] {
+            let protons = Search { projectile: Some(term.to_string()), ..Search::default() };
+            let reactions: Vec<&str> = protons.filter(&data).iter().map(|entry| &*entry.reaction).collect();
+            assert_eq!(reactions, ["(p,2n)"], "{}", term);
+        }
+
+        let helions = Search { projectile: Some("3He".to_string()), ..Search::default() };
+        let keys: Vec<String> = helions.filter(&data).iter().map(|entry| entry.key().to_string()).collect();
+        assert_eq!(keys, ["Li_7_TENDL-2019_h_4_294K"]);
+        assert_eq!(&*data.get(6).unwrap().reaction, "(3He,level)");
+    }
+}
@@ -0,0 +1,144 @@
+//! The reactions picked for plotting, shared by the table, the plot and the downloads.
+use crate::catalog::EntryKey;
+
+/// Selected entries by key, each at most once and in the order they were selected.
+///
+/// Rows are matched by [`EntryKey`] rather than position, so a row shows the
+/// same checked state whatever the search boxes filter out around it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    keys: Vec<EntryKey>,
+}
+
+impl Selection {
+    pub fn contains(&self, key: &EntryKey) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Adds `key` at the end, returning `false` if it was already selected.
+    pub fn insert(&mut self, key: EntryKey) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        self.keys.push(key);
+        true
+    }
+
+    /// Returns `false` if `key` was not selected.
+    pub fn remove(&mut self, key: &EntryKey) -> bool {
+        let len = self.keys.len();
+        self.keys.retain(|selected| selected != key);
+        self.keys.len() != len
+    }
+
+    /// Selects `key` if it was not selected and deselects it otherwise, returning whether it is now selected.
+    pub fn toggle(&mut self, key: EntryKey) -> bool {
+        !self.remove(&key) && self.insert(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The selected keys in selection order.
+    pub fn keys(&self) -> &[EntryKey] {
+        &self.keys
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, EntryKey> {
+        self.keys.iter()
+    }
+}
+
+impl FromIterator<EntryKey> for Selection {
+    fn from_iter<I: IntoIterator<Item = EntryKey>>(keys: I) -> Self {
+        let mut selection = Self::default();
+        for key in keys {
+            selection.insert(key);
+        }
+        selection
+    }
+}
+
+impl<'a> IntoIterator for &'a Selection {
+    type Item = &'a EntryKey;
+    type IntoIter = std::slice::Iter<'a, EntryKey>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{load_data_from_csv, Data};
+    use crate::search::Search;
+
+    const CATALOG: &str = "id,element,nucleons,library,incident_particle,mt,temperature
+0,Fe,54,ENDFB-8.0,n,102,294
+1,Fe,56,ENDFB-8.0,n,102,294
+2,Li,6,FENDL-3.2c,n,105,294
+3,Li,7,FENDL-3.2c,n,16,294
+4,Fe,56,FENDL-3.2c,n,16,294
+";
+
+    fn catalog() -> Data {
+        Data::new(load_data_from_csv(CATALOG).unwrap()).unwrap()
+    }
+
+    fn checked(selection: &Selection, search: &Search, data: &Data) -> Vec<bool> {
+        search.filter(data).iter().map(|entry| selection.contains(&entry.key())).collect()
+    }
+
+    #[test]
+    fn keeps_selection_order_without_duplicates() {
+        let data = catalog();
+        let keys: Vec<EntryKey> = data.iter().map(|entry| entry.key()).collect();
+        let mut selection: Selection = [keys[3].clone(), keys[0].clone(), keys[3].clone()].into_iter().collect();
+        assert_eq!(selection.keys(), &[keys[3].clone(), keys[0].clone()]);
+
+        assert!(selection.toggle(keys[2].clone()));
+        assert!(!selection.toggle(keys[0].clone()));
+        assert!(selection.toggle(keys[0].clone()));
+        assert_eq!(selection.keys(), &[keys[3].clone(), keys[2].clone(), keys[0].clone()]);
+    }
+
+    #[test]
+    fn filtered_rows_are_checked_by_key_not_position() {
+        let data = catalog();
+        let mut selection = Selection::default();
+        // The first lithium entry is the third row unfiltered but the first once filtered to Li
+        selection.toggle(data.get(2).unwrap().key());
+
+        let lithium = Search { element: Some("li".to_string()), ..Search::default() };
+        assert_eq!(checked(&selection, &lithium, &data), [true, false]);
+        assert_eq!(checked(&selection, &Search::default(), &data), [false, false, true, false, false]);
+
+        let iron = Search { element: Some("Fe".to_string()), ..Search::default() };
+        assert_eq!(checked(&selection, &iron, &data), [false, false, false]);
+    }
+
+    #[test]
+    fn selection_survives_filters_that_hide_it() {
+        let data = catalog();
+        let mut selection = Selection::default();
+        selection.toggle(data.get(4).unwrap().key());
+        selection.toggle(data.get(0).unwrap().key());
+
+        // Hiding a selected row neither deselects it nor moves the check to another row
+        let mt_16 = Search { mt: Some("16".to_string()), ..Search::default() };
+        assert_eq!(checked(&selection, &mt_16, &data), [false, true]);
+        let endf = Search { library: Some("ENDFB".to_string()), ..Search::default() };
+        assert_eq!(checked(&selection, &endf, &data), [true, false]);
+        assert_eq!(selection.keys(), &[data.get(4).unwrap().key(), data.get(0).unwrap().key()]);
+    }
+}
@@ -0,0 +1,173 @@
+//! Backends the reaction JSON files can be read from.
+//!
+//! Every backend serves the files named by [`convert_string`], so a mirror or
+//! an offline copy of the openmc-data-storage repositories only needs the same
+//! file names to be usable.
+use async_trait::async_trait;
+use tracing::debug;
+
+use crate::catalog::Entry;
+use crate::error::FetchError;
+use crate::fetch::{convert_string, ReactionData};
+use crate::library::registry;
+
+/// Somewhere reaction data can be read from.
+#[async_trait(?Send)]
+pub trait DataSource {
+    /// Where the entry would be read from, `None` if this source can not serve it.
+    fn locate(&self, entry: &Entry) -> Option<String>;
+
+    /// Reads the energies and cross sections of one entry.
+    async fn fetch(&self, entry: &Entry) -> Result<ReactionData, FetchError>;
+}
+
+async fn fetch_json(url: String) -> Result<ReactionData, FetchError> {
+    debug!("downloading {}", url);
+    let response = reqwest::get(&url).await.map_err(|e| FetchError::Network(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(FetchError::Status { url, status: response.status().as_u16() });
+    }
+    response.json().await.map_err(|e| FetchError::Parse(format!("{}: {}", url, e)))
+}
+
+/// The repository of each library in the [`registry`], by default the openmc-data-storage JSON repositories on GitHub.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GitHub;
+
+#[async_trait(?Send)]
+impl DataSource for GitHub {
+    fn locate(&self, entry: &Entry) -> Option<String> {
+        registry()
+            .get(&entry.library)
+            .filter(|library| library.has_temperature(&entry.temperature))
+            .and_then(|library| library.file_url(entry))
+    }
+
+    async fn fetch(&self, entry: &Entry) -> Result<ReactionData, FetchError> {
+        let url = self.locate(entry).ok_or_else(|| FetchError::UnsupportedLibrary(format!("{} for {}", entry.library, entry.key())))?;
+        fetch_json(url).await
+    }
+}
+
+/// A web server holding all the JSON files directly below one base URL, such as an internal mirror.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BaseUrl {
+    pub base_url: String,
+}
+
+impl BaseUrl {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self { base_url: base_url.into().trim_end_matches('/').to_string() }
+    }
+}
+
+#[async_trait(?Send)]
+impl DataSource for BaseUrl {
+    fn locate(&self, entry: &Entry) -> Option<String> {
+        Some(format!("{}/{}.json", self.base_url, convert_string(entry)))
+    }
+
+    async fn fetch(&self, entry: &Entry) -> Result<ReactionData, FetchError> {
+        let url = self.locate(entry).ok_or_else(|| FetchError::UnsupportedLibrary(format!("{} for {}", entry.library, entry.key())))?;
+        fetch_json(url).await
+    }
+}
+
+/// A directory on disk holding all the JSON files, for air-gapped machines.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalDirectory {
+    pub path: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LocalDirectory {
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait(?Send)]
+impl DataSource for LocalDirectory {
+    fn locate(&self, entry: &Entry) -> Option<String> {
+        let file = self.path.join(format!("{}.json", convert_string(entry)));
+        Some(file.to_string_lossy().into_owned())
+    }
+
+    async fn fetch(&self, entry: &Entry) -> Result<ReactionData, FetchError> {
+        let file = self.locate(entry).ok_or_else(|| FetchError::UnsupportedLibrary(format!("{} for {}", entry.library, entry.key())))?;
+        debug!("reading {}", file);
+        let contents = std::fs::read_to_string(&file).map_err(|e| FetchError::Io(format!("{}: {}", file, e)))?;
+        serde_json::from_str(&contents).map_err(|e| FetchError::Parse(format!("{}: {}", file, e)))
+    }
+}
+
+/// JSON files picked by the user in the browser, read lazily as they are needed.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PickedFiles {
+    pub files: std::collections::HashMap<String, web_sys::File>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl PickedFiles {
+    /// Collects the files of a file or directory `<input>`, keyed by file name.
+    pub fn from_file_list(file_list: &web_sys::FileList) -> Self {
+        let files = (0..file_list.length())
+            .filter_map(|i| file_list.item(i))
+            .filter(|file| file.name().ends_with(".json"))
+            .map(|file| (file.name(), file))
+            .collect();
+        Self { files }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[async_trait(?Send)]
+impl DataSource for PickedFiles {
+    fn locate(&self, entry: &Entry) -> Option<String> {
+        let name = format!("{}.json", convert_string(entry));
+        self.files.contains_key(&name).then_some(name)
+    }
+
+    async fn fetch(&self, entry: &Entry) -> Result<ReactionData, FetchError> {
+        let name = self.locate(entry).ok_or_else(|| FetchError::Io(format!("{}.json was not among the picked files", convert_string(entry))))?;
+        let file = &self.files[&name];
+        debug!("reading picked file {}", name);
+        let text = wasm_bindgen_futures::JsFuture::from(file.text())
+            .await
+            .map_err(|e| FetchError::Io(format!("{}: {:?}", name, e)))?;
+        let contents = text.as_string().ok_or_else(|| FetchError::Io(format!("{}: not a text file", name)))?;
+        serde_json::from_str(&contents).map_err(|e| FetchError::Parse(format!("{}: {}", name, e)))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::catalog::EntryKey;
+
+    #[tokio::test]
+    async fn local_directories_read_files_by_name_and_report_missing_ones() {
+        let directory = std::env::temp_dir().join(format!("xsplot-source-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("Fe_56_FENDL-3.2c_n_102_294K.json"), r#"{"energy": [1.0, 2.0], "cross section": [3.0, 4.0]}"#).unwrap();
+        let source = LocalDirectory::new(&directory);
+
+        let entry = Entry::from_key(&"Fe_56_FENDL-3.2c_n_102_294K".parse::<EntryKey>().unwrap());
+        assert_eq!(source.locate(&entry).unwrap(), directory.join("Fe_56_FENDL-3.2c_n_102_294K.json").to_string_lossy());
+        let data = source.fetch(&entry).await.unwrap();
+        assert_eq!(data.energy_values, [1.0, 2.0]);
+        assert_eq!(data.cross_section_values, [3.0, 4.0]);
+
+        let missing = Entry::from_key(&"Fe_56_FENDL-3.2c_n_16_294K".parse::<EntryKey>().unwrap());
+        let missing_file = format!("{}: ", directory.join("Fe_56_FENDL-3.2c_n_16_294K.json").display());
+        let result = source.fetch(&missing).await;
+        std::fs::remove_dir_all(&directory).unwrap();
+        match result {
+            Err(FetchError::Io(message)) => assert!(message.starts_with(&missing_file)),
+            other => panic!("expected a missing file error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Drawing a plotly figure as an SVG image without a browser, for saving
+//! figures from the command line.
+//!
+//! Only what [`figure`](crate::figure) puts in a plot is drawn: line traces with
+//! their names and colours, log or linear axes with titles, and a legend to the
+//! right. Axis ranges span the data as plotly's autorange does, with a little
+//! headroom on the y axis. Points that a log axis cannot show are left out and
+//! break the line, as plotly does.
+use std::fmt::Write as _;
+
+use plotly::Plot;
+
+use crate::figure::{AxisData, FigureData};
+
+const GRID_COLOUR: &str = "#e5e5e5";
+const AXIS_COLOUR: &str = "#444";
+
+/// Roughly how wide a character is, in font sizes, for laying out text the
+/// image cannot measure.
+const CHARACTER_WIDTH: f64 = 0.6;
+
+/// About how many ticks an axis gets.
+const TICKS: f64 = 6.0;
+
+/// One axis of the plot, mapping data onto a span of the image.
+struct Axis {
+    title: String,
+    is_log: bool,
+    /// Ends of the axis, as powers of ten on a log axis
+    low: f64,
+    high: f64,
+    /// Image coordinates of `low` and `high`
+    start: f64,
+    end: f64,
+}
+
+impl Axis {
+    fn new(axis: &AxisData, values: impl Iterator<Item = f64>, headroom: f64) -> Self {
+        let AxisData { title, is_log } = axis.clone();
+        let (mut low, mut high) = values
+            .filter(|value| value.is_finite() && (!is_log || *value > 0.0))
+            .map(|value| if is_log { value.log10() } else { value })
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| (low.min(value), high.max(value)));
+        if low > high {
+            (low, high) = if is_log { (0.0, 1.0) } else { (-1.0, 1.0) };
+        } else if low == high {
+            (low, high) = (low - 0.5, high + 0.5);
+        }
+        let pad = (high - low) * headroom;
+        Self { title, is_log, low: low - pad, high: high + pad, start: 0.0, end: 0.0 }
+    }
+
+    /// Where `value` is drawn, `None` for values a log axis cannot show.
+    fn position(&self, value: f64) -> Option<f64> {
+        let value = if self.is_log { (value > 0.0).then(|| value.log10())? } else { value };
+        value.is_finite().then(|| self.start + (value - self.low) / (self.high - self.low) * (self.end - self.start))
+    }
+
+    /// Tick values with their labels.
+    fn ticks(&self) -> Vec<(f64, String)> {
+        if self.is_log {
+            let step = ((self.high - self.low) / TICKS).ceil().max(1.0);
+            let first = (self.low / step).ceil() as i32;
+            let last = (self.high / step).floor() as i32;
+            let decades: Vec<(f64, String)> = (first..=last)
+                .map(|i| {
+                    let exponent = i * step as i32;
+                    (10f64.powi(exponent), format!("10<tspan dy=\"-0.5em\" font-size=\"70%\">{}</tspan>", exponent))
+                })
+                .collect();
+            if decades.len() >= 2 {
+                return decades;
+            }
+            // Less than a decade shown, so tick it linearly instead
+            let linear = Axis { is_log: false, low: 10f64.powf(self.low), high: 10f64.powf(self.high), title: String::new(), start: 0.0, end: 0.0 };
+            return linear.ticks();
+        }
+        let step = nice_step((self.high - self.low) / TICKS);
+        let first = (self.low / step).ceil() as i64;
+        let last = (self.high / step).floor() as i64;
+        (first..=last).map(|i| (i as f64 * step, tick_label(i as f64 * step, step))).collect()
+    }
+}
+
+/// The 1, 2 or 5 times a power of ten nearest above `step`.
+fn nice_step(step: f64) -> f64 {
+    let magnitude = 10f64.powf(step.log10().floor());
+    let fraction = step / magnitude;
+    magnitude * if fraction <= 1.0 { 1.0 } else if fraction <= 2.0 { 2.0 } else if fraction <= 5.0 { 5.0 } else { 10.0 }
+}
+
+/// A tick value with as many decimals as the step needs, in exponent form when very large or small.
+fn tick_label(value: f64, step: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = value.abs().log10().floor();
+    if !(-3.0..5.0).contains(&magnitude) {
+        let decimals = (magnitude - step.log10().floor()).max(0.0) as usize;
+        return format!("{:.*e}", decimals, value);
+    }
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The figure as a standalone SVG image the size of its layout.
+pub fn to_svg(plot: &Plot) -> Result<String, String> {
+    let FigureData { width, height, font_family, font_size, x_axis, y_axis, traces } = FigureData::from_plot(plot)?;
+    let mut x_axis = Axis::new(&x_axis, traces.iter().flat_map(|trace| trace.x.iter().copied()), 0.0);
+    let mut y_axis = Axis::new(&y_axis, traces.iter().flat_map(|trace| trace.y.iter().copied()), 0.05);
+
+    // Room for the tick labels and titles on the left and below, and the legend on the right
+    let longest_name = traces.iter().map(|trace| trace.name.chars().count()).max().unwrap_or(0) as f64;
+    let legend_width = if traces.is_empty() { 0.0 } else { longest_name * CHARACTER_WIDTH * font_size + 3.5 * font_size };
+    let left = 7.0 * font_size;
+    let right = width - legend_width - font_size;
+    let top = 2.0 * font_size;
+    let bottom = height - 4.0 * font_size;
+    if right <= left || bottom <= top {
+        return Err(format!("a {}x{} figure is too small for its labels and legend", width, height));
+    }
+    (x_axis.start, x_axis.end) = (left, right);
+    (y_axis.start, y_axis.end) = (bottom, top);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" font-family=\"{}\" font-size=\"{font_size}\">",
+        escape(&font_family)
+    );
+    let _ = writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>");
+    let _ = writeln!(svg, "<clipPath id=\"plot-area\"><rect x=\"{left:.2}\" y=\"{top:.2}\" width=\"{:.2}\" height=\"{:.2}\"/></clipPath>", right - left, bottom - top);
+
+    // Grid and tick labels
+    for (value, label) in x_axis.ticks() {
+        let Some(x) = x_axis.position(value) else { continue };
+        let _ = writeln!(svg, "<line x1=\"{x:.2}\" y1=\"{top:.2}\" x2=\"{x:.2}\" y2=\"{bottom:.2}\" stroke=\"{GRID_COLOUR}\"/>");
+        let _ = writeln!(svg, "<text x=\"{x:.2}\" y=\"{:.2}\" text-anchor=\"middle\" fill=\"{AXIS_COLOUR}\">{label}</text>", bottom + 1.5 * font_size);
+    }
+    for (value, label) in y_axis.ticks() {
+        let Some(y) = y_axis.position(value) else { continue };
+        let _ = writeln!(svg, "<line x1=\"{left:.2}\" y1=\"{y:.2}\" x2=\"{right:.2}\" y2=\"{y:.2}\" stroke=\"{GRID_COLOUR}\"/>");
+        let _ = writeln!(svg, "<text x=\"{:.2}\" y=\"{y:.2}\" dy=\"0.35em\" text-anchor=\"end\" fill=\"{AXIS_COLOUR}\">{label}</text>", left - 0.5 * font_size);
+    }
+    let _ = writeln!(svg, "<rect x=\"{left:.2}\" y=\"{top:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"none\" stroke=\"{AXIS_COLOUR}\"/>", right - left, bottom - top);
+
+    // Axis titles
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\" fill=\"{AXIS_COLOUR}\">{}</text>",
+        (left + right) / 2.0,
+        height - font_size,
+        escape(&x_axis.title)
+    );
+    let (title_x, title_y) = (1.5 * font_size, (top + bottom) / 2.0);
+    let _ = writeln!(
+        svg,
+        "<text x=\"{title_x:.2}\" y=\"{title_y:.2}\" transform=\"rotate(-90 {title_x:.2} {title_y:.2})\" text-anchor=\"middle\" fill=\"{AXIS_COLOUR}\">{}</text>",
+        escape(&y_axis.title)
+    );
+
+    // Curves, broken wherever a point cannot be drawn
+    let _ = writeln!(svg, "<g clip-path=\"url(#plot-area)\" fill=\"none\" stroke-linejoin=\"round\">");
+    for trace in &traces {
+        let mut segments: Vec<Vec<String>> = vec![Vec::new()];
+        for (x, y) in trace.x.iter().zip(&trace.y) {
+            match (x_axis.position(*x), y_axis.position(*y)) {
+                (Some(x), Some(y)) => segments.last_mut().unwrap().push(format!("{x:.2},{y:.2}")),
+                _ if !segments.last().unwrap().is_empty() => segments.push(Vec::new()),
+                _ => {}
+            }
+        }
+        for points in segments.iter().filter(|points| !points.is_empty()) {
+            let _ = writeln!(svg, "<polyline points=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>", points.join(" "), escape(&trace.colour), trace.line_width);
+        }
+    }
+    let _ = writeln!(svg, "</g>");
+
+    // Legend
+    for (i, trace) in traces.iter().enumerate() {
+        let x = right + font_size;
+        let y = top + (i as f64 + 0.5) * 1.5 * font_size;
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{x:.2}\" y1=\"{y:.2}\" x2=\"{:.2}\" y2=\"{y:.2}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            x + 2.0 * font_size,
+            escape(&trace.colour),
+            trace.line_width
+        );
+        let _ = writeln!(svg, "<text x=\"{:.2}\" y=\"{y:.2}\" dy=\"0.35em\" fill=\"{AXIS_COLOUR}\">{}</text>", x + 2.5 * font_size, escape(&trace.name));
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::XsCache;
+    use crate::figure::{figure, FigureStyle};
+
+    #[test]
+    fn log_axes_break_lines_at_points_they_cannot_show() {
+        let cache = XsCache {
+            keys: Vec::new(),
+            energy_values: vec![vec![1.0, 10.0, 100.0, 1000.0, 10000.0]],
+            cross_section_values: vec![vec![1.0, 2.0, 0.0, 4.0, 5.0]],
+            checkbox_selected: vec![true],
+            labels: vec!["Li6 (n,t) <ENDF>".to_string()],
+        };
+        let svg = to_svg(&figure(&cache, &["#1f77b4"], true, true, &FigureStyle::default())).unwrap();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"900\" height=\"600\""));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("stroke=\"#1f77b4\" stroke-width=\"2\""));
+        assert!(svg.contains(">Li6 (n,t) &lt;ENDF&gt;</text>"));
+        assert!(svg.contains(">Energy [eV]</text>"));
+        // A tick per decade of energy, the cross sections span less than one and are ticked linearly
+        assert_eq!(svg.matches("<tspan").count(), 5);
+    }
+
+    #[test]
+    fn linear_ticks_are_round_numbers() {
+        assert_eq!(nice_step(0.13), 0.2);
+        assert_eq!(nice_step(3.0e6), 5.0e6);
+        assert_eq!(tick_label(0.25, 0.05), "0.25");
+        assert_eq!(tick_label(2.0e7, 5.0e6), "2.0e7");
+    }
+}
@@ -0,0 +1,44 @@
+use gloo_storage::{LocalStorage, Storage};
+use tracing::error;
+use xsplot::library::{set_registry, Registry};
+
+const LIBRARIES_KEY: &str = "libraries.user";
+const ENABLED_KEY: &str = "libraries.enabled";
+
+/// The libraries the user added or changed, kept in local storage.
+pub(crate) fn user_libraries() -> Option<Registry> {
+    LocalStorage::get(LIBRARIES_KEY).ok()
+}
+
+/// Layers the user's libraries over the bundled registry, done once before the app renders.
+pub(crate) fn apply_user_libraries() {
+    let mut registry = Registry::default();
+    if let Some(user) = user_libraries() {
+        registry.merge(user);
+    }
+    set_registry(registry);
+}
+
+/// Checks and stores a registry file picked by the user, taking effect on the next page load.
+pub(crate) fn save_user_libraries(json: &str) -> Result<(), String> {
+    let registry = Registry::from_json(json).map_err(|e| e.to_string())?;
+    LocalStorage::set(LIBRARIES_KEY, registry).map_err(|e| {
+        error!("Error saving libraries: {:?}", e);
+        e.to_string()
+    })
+}
+
+pub(crate) fn clear_user_libraries() {
+    LocalStorage::delete(LIBRARIES_KEY);
+}
+
+/// The catalog libraries the user last chose to list.
+pub(crate) fn enabled_libraries() -> Option<Vec<String>> {
+    LocalStorage::get(ENABLED_KEY).ok()
+}
+
+pub(crate) fn save_enabled_libraries(names: &[String]) {
+    if let Err(e) = LocalStorage::set(ENABLED_KEY, names) {
+        error!("Error saving enabled libraries: {:?}", e);
+    }
+}
@@ -1,2 +1,2 @@
 pub(crate) mod theme;
-pub mod mock_data;
+pub(crate) mod libraries;